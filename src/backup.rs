@@ -0,0 +1,191 @@
+//! Create and locate backups of the storage file.
+//!
+//! Besides the backup written by `migrate`, a configurable number of rolling
+//! backups `<storage>.1` to `<storage>.N` is kept, `.1` being the most recent.
+//! Backups are written next to the storage unless a backup dir is configured.
+
+use crate::errors::*;
+use chrono::{DateTime, Local};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// File name of the storage at `path`, the prefix of all its backups.
+fn storage_name(path: &Path) -> Option<String> {
+    path.file_name().map(|n| n.to_string_lossy().to_string())
+}
+
+/// Directory holding the backups of the storage at `path`, `dir` if given.
+fn backup_dir(path: &Path, dir: Option<&Path>) -> PathBuf {
+    match (dir, path.parent()) {
+        (Some(d), _) => d.to_path_buf(),
+        (None, Some(p)) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Path of the backup written by `migrate` at `time`.
+///
+/// Next to the storage it's `<storage>.bak`, in a backup `dir` the name
+/// contains the time so successive migrations don't overwrite each other.
+pub(crate) fn migration_backup<P: AsRef<Path>>(
+    path: P,
+    dir: Option<&Path>,
+    time: DateTime<Local>,
+) -> PathBuf {
+    let path = path.as_ref();
+    match (dir, storage_name(path)) {
+        (Some(d), Some(name)) => d.join(format!("{}.{}.bak", name, time.format("%Y%m%d-%H%M%S"))),
+        _ => PathBuf::from(path.to_string_lossy().to_string() + ".bak"),
+    }
+}
+
+/// Path of the `n`th rolling backup, `1` being the most recent.
+pub(crate) fn rolling_backup<P: AsRef<Path>>(path: P, n: u8, dir: Option<&Path>) -> PathBuf {
+    let path = path.as_ref();
+    match (dir, storage_name(path)) {
+        (Some(d), Some(name)) => d.join(format!("{}.{}", name, n)),
+        _ => PathBuf::from(format!("{}.{}", path.to_string_lossy(), n)),
+    }
+}
+
+/// Copy the storage at `path` to the most recent rolling backup in `dir`,
+/// keeping at most `count` backups.
+///
+/// Does nothing if `count` is zero or there is no storage yet.
+pub(crate) fn rotate<P: AsRef<Path>>(path: P, count: u8, dir: Option<&Path>) -> Result<()> {
+    let path = path.as_ref();
+    if count == 0 || !path.exists() {
+        return Ok(());
+    }
+    if let Some(d) = dir {
+        std::fs::create_dir_all(d)
+            .wrap_err_with(|| format!("Failed to create backup dir '{}'", d.display()))?;
+    }
+    for n in (1..count).rev() {
+        let from = rolling_backup(path, n, dir);
+        if from.exists() {
+            std::fs::rename(&from, rolling_backup(path, n + 1, dir))
+                .wrap_err_with(|| format!("Failed to rotate backup '{}'", from.display()))?;
+        }
+    }
+    let latest = rolling_backup(path, 1, dir);
+    log::debug!("Backing up {} to {}", path.display(), latest.display());
+    std::fs::copy(path, &latest)
+        .wrap_err_with(|| format!("Failed to write backup '{}'", latest.display()))?;
+    Ok(())
+}
+
+/// Backups of `path` in `dir` which exist on disk, rolling and migration ones.
+fn existing_backups(path: &Path, dir: Option<&Path>) -> Vec<PathBuf> {
+    let name = match storage_name(path) {
+        Some(n) => n + ".",
+        None => return Vec::new(),
+    };
+    let is_backup =
+        |suffix: &str| suffix.parse::<u8>().is_ok() || (dir.is_some() && suffix.ends_with(".bak"));
+    std::fs::read_dir(backup_dir(path, dir))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| {
+                    e.file_name()
+                        .to_string_lossy()
+                        .strip_prefix(&name)
+                        .map(is_backup)
+                        .unwrap_or(false)
+                })
+                .map(|e| match dir {
+                    Some(d) => d.join(e.file_name()),
+                    None => path.with_file_name(e.file_name()),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// All existing backups of the storage at `path` next to it and in `dir`,
+/// most recently modified first.
+pub(crate) fn backups<P: AsRef<Path>>(path: P, dir: Option<&Path>) -> Vec<PathBuf> {
+    let path = path.as_ref();
+    let mut candidates = existing_backups(path, None);
+    candidates.push(PathBuf::from(path.to_string_lossy().to_string() + ".bak"));
+    if dir.is_some() {
+        candidates.extend(existing_backups(path, dir));
+    }
+    candidates.sort();
+    candidates.dedup();
+    let mut found: Vec<(SystemTime, PathBuf)> = candidates
+        .into_iter()
+        .filter_map(|p| {
+            let modified = std::fs::metadata(&p).ok()?.modified().ok()?;
+            Some((modified, p))
+        })
+        .collect();
+    found.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    found.into_iter().map(|(_, p)| p).collect()
+}
+
+/// The most recently modified backup of the storage at `path` next to it or
+/// in `dir`, if any.
+pub(crate) fn latest<P: AsRef<Path>>(path: P, dir: Option<&Path>) -> Option<PathBuf> {
+    backups(path, dir).into_iter().next()
+}
+
+#[test]
+fn rotate_keeps_count() {
+    let dir = std::env::temp_dir().join(format!("stempel-backup-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let storage = dir.join("stempel.json");
+
+    rotate(&storage, 2, None).expect("Rotating without storage works");
+    assert!(backups(&storage, None).is_empty());
+
+    for i in 0..4 {
+        std::fs::write(&storage, i.to_string()).unwrap();
+        rotate(&storage, 2, None).expect("Rotating works");
+    }
+    assert_eq!(
+        std::fs::read_to_string(rolling_backup(&storage, 1, None)).unwrap(),
+        "3"
+    );
+    assert_eq!(
+        std::fs::read_to_string(rolling_backup(&storage, 2, None)).unwrap(),
+        "2"
+    );
+    assert!(!rolling_backup(&storage, 3, None).exists());
+    assert_eq!(backups(&storage, None).len(), 2);
+
+    rotate(&storage, 0, None).expect("Disabled rotating works");
+    assert_eq!(backups(&storage, None).len(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn backups_in_backup_dir() {
+    let dir = std::env::temp_dir().join(format!("stempel-backup-dir-{}", std::process::id()));
+    let backup_dir = dir.join("backups");
+    std::fs::create_dir_all(&dir).unwrap();
+    let storage = dir.join("stempel.json");
+    std::fs::write(&storage, "1").unwrap();
+
+    rotate(&storage, 2, Some(&backup_dir)).expect("Rotating creates the dir");
+    assert!(backup_dir.join("stempel.json.1").exists());
+    assert!(!rolling_backup(&storage, 1, None).exists());
+
+    let morning = Local::now();
+    let first = migration_backup(&storage, Some(&backup_dir), morning);
+    let second = migration_backup(
+        &storage,
+        Some(&backup_dir),
+        morning + chrono::Duration::seconds(1),
+    );
+    assert_ne!(first, second);
+    assert!(first.starts_with(&backup_dir));
+    std::fs::write(&first, "0").unwrap();
+    assert_eq!(backups(&storage, Some(&backup_dir)).len(), 2);
+    assert_eq!(
+        migration_backup(&storage, None, morning),
+        dir.join("stempel.json.bak")
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
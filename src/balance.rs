@@ -12,17 +12,60 @@ use std::fmt::Display;
 use std::fs::{File, OpenOptions};
 use std::ops::Add;
 use std::ops::AddAssign;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{
     collections::BTreeMap,
     io::{BufReader, Read, Write},
+    ops::Bound,
 };
 
+use itertools::Itertools;
+
 use crate::cli_input::YesNo;
 use crate::errors::*;
 
 use crate::storage::WorkStorage;
 
+/// Seconds a start of work or break may lie in the future before it is rejected.
+pub(crate) const MAX_FUTURE_SECS: i64 = 5 * 60;
+
+/// Reject `time` if it lies further than `MAX_FUTURE_SECS` after `now`.
+fn ensure_not_future(time: DateTime<Utc>, now: DateTime<Utc>, action: &'static str) -> Result<()> {
+    if time.signed_duration_since(now) > Duration::seconds(MAX_FUTURE_SECS) {
+        bail!(TimeError::InFuture { action, time });
+    }
+    Ok(())
+}
+
+/// First and last day of `month` in `year`.
+pub(crate) fn month_bounds(year: i32, month: Month) -> Result<(NaiveDate, NaiveDate)> {
+    let first = NaiveDate::from_ymd_opt(year, month.number_from_month(), 1)
+        .ok_or(eyre!("Could not construct range"))?;
+    let last = first
+        .checked_add_months(chrono::Months::new(1))
+        .and_then(|d| d.pred_opt())
+        .ok_or(eyre!("Could not construct range"))?;
+    Ok((first, last))
+}
+
+/// Local day of the work stopped at `stop`. Work stopped right at midnight
+/// counts for the day before, e.g. the first part of work split over midnight.
+pub(crate) fn entry_day(stop: &DateTime<Utc>) -> NaiveDate {
+    (*stop - Duration::nanoseconds(1))
+        .with_timezone(&Local)
+        .date_naive()
+}
+
+/// Format the clock times from `start` to `stop` like `09:00–17:30`, with the
+/// date on the stop side if the span crosses midnight.
+pub fn format_span(start: DateTime<Local>, stop: DateTime<Local>) -> String {
+    if start.date_naive() == stop.date_naive() {
+        format!("{}–{}", start.format("%H:%M"), stop.format("%H:%M"))
+    } else {
+        format!("{}–{}", start.format("%H:%M"), stop.format("%d.%m. %H:%M"))
+    }
+}
+
 fn nanoseconds(_dur: &Duration) -> i32 {
     0i32
 }
@@ -69,28 +112,46 @@ pub(crate) struct DurationDef {
     inner: Duration,
 }
 
-impl DurationDef {
-    pub fn zero() -> Self {
-        Self {
-            inner: Duration::zero(),
-        }
-    }
-}
-
 impl AsRef<Duration> for DurationDef {
     fn as_ref(&self) -> &Duration {
         &self.inner
     }
 }
 
+/// Formats as `HH:MMh`, the alternate form `{:#}` as `HH:MM:SSh`.
 impl Display for DurationDef {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.inner < Duration::zero() {
+            "-"
+        } else {
+            ""
+        };
+        let abs = self.inner.abs();
         write!(
             f,
-            "{:02}:{:02}h",
-            self.inner.num_hours(),
-            self.inner.num_minutes() % 60
-        )
+            "{}{:02}:{:02}",
+            sign,
+            abs.num_hours(),
+            abs.num_minutes() % 60
+        )?;
+        if f.alternate() {
+            write!(f, ":{:02}", abs.num_seconds() % 60)?;
+        }
+        write!(f, "h")
+    }
+}
+
+impl DurationDef {
+    /// Format as decimal hours like `7.50`, rounded half away from zero to
+    /// hundredths of an hour.
+    pub fn decimal(&self) -> String {
+        let sign = if self.inner < Duration::zero() {
+            "-"
+        } else {
+            ""
+        };
+        let hundredths = (self.inner.num_seconds().abs() * 100 + 1800) / 3600;
+        format!("{}{}.{:02}", sign, hundredths / 100, hundredths % 100)
     }
 }
 
@@ -108,11 +169,302 @@ impl AddAssign for DurationDef {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+/// A finished work period with its net duration and the breaks taken during it.
+///
+/// Serializes to the plain duration if there were no breaks, so storages
+/// written before breaks were kept are read without migration.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub(crate) struct WorkEntry {
+    #[serde(flatten)]
+    pub duration: DurationDef,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub breaks: Vec<(DateTime<Utc>, DurationDef)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Sorted tags, e.g. the client the work was for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl WorkEntry {
+    /// Add the duration and breaks of `other` to this entry.
+    pub fn merge(&mut self, other: WorkEntry) {
+        self.duration += other.duration;
+        self.breaks.extend(other.breaks);
+        self.breaks.sort_by_key(|(s, _)| *s);
+        self.note = match (self.note.take(), other.note) {
+            (Some(a), Some(b)) => Some(format!("{}; {}", a, b)),
+            (a, b) => a.or(b),
+        };
+        self.tags.extend(other.tags);
+        self.tags.sort();
+        self.tags.dedup();
+    }
+
+    /// Start of this entry stopped at `stop`, i.e. before its work and breaks.
+    pub fn start(&self, stop: DateTime<Utc>) -> DateTime<Utc> {
+        stop - self.duration.inner - self.break_sum()
+    }
+
+    /// Sum of the breaks taken during this entry.
+    pub fn break_sum(&self) -> Duration {
+        self.breaks
+            .iter()
+            .fold(Duration::zero(), |acc, (_, d)| acc + d.inner)
+    }
+}
+
+impl From<DurationDef> for WorkEntry {
+    fn from(duration: DurationDef) -> Self {
+        Self {
+            duration,
+            breaks: Vec::new(),
+            note: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+impl From<&WorkEntry> for Duration {
+    fn from(entry: &WorkEntry) -> Self {
+        entry.duration.inner
+    }
+}
+
+/// Kind of a day marked as off, it counts as meeting the daily working hours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DayKind {
+    Sick,
+    Vacation,
+    Holiday,
+}
+
+impl Display for DayKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DayKind::Sick => write!(f, "sick day"),
+            DayKind::Vacation => write!(f, "vacation"),
+            DayKind::Holiday => write!(f, "holiday"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Config {
     pub month_stats: u8,
     pub daily_hours: Option<u8>,
+    pub weekly_hours: Option<u8>,
     pub weekly_stats: Option<bool>,
+    pub split_over_midnight: Option<bool>,
+    pub initial_overtime_minutes: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub holidays: Vec<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vacations: Vec<NaiveDate>,
+    pub backup_count: Option<u8>,
+    pub backup_dir: Option<PathBuf>,
+    pub default_profile: Option<String>,
+    pub warn_day_hours: Option<u8>,
+    pub warn_break_hours: Option<u8>,
+    pub show_seconds: Option<bool>,
+    pub warn_idle_hours: Option<u8>,
+    pub hourly_rate: Option<f64>,
+    pub currency: Option<String>,
+    pub display_round_quarter: Option<bool>,
+    pub tracking_start_date: Option<NaiveDate>,
+    pub pretty_json: Option<bool>,
+    pub overtime_cap_hours: Option<u8>,
+    pub daily_grace_minutes: Option<u8>,
+    pub cap_warn_percent: Option<u8>,
+    pub notifications: Option<bool>,
+    pub locale: Option<String>,
+    pub decimal_hours: Option<bool>,
+    pub auto_stop_at: Option<NaiveTime>,
+    pub period_start_day: Option<u8>,
+    pub clock_skew_hours: Option<u8>,
+    pub avg_window_days: Option<u16>,
+    pub merge_same_day: Option<bool>,
+}
+
+/// Default hours of work after which stopping warns about a long day.
+pub const WARN_DAY_HOURS: u8 = 24;
+
+/// Default hours of a break after which stopping it warns about a long break.
+pub const WARN_BREAK_HOURS: u8 = 8;
+
+/// Default hours since the start after which a forgotten stop is suspected.
+pub const WARN_IDLE_HOURS: u8 = 16;
+
+/// Default hours a start may lie before the stop of the latest entry before
+/// the clock is suspected to have gone backwards.
+pub const CLOCK_SKEW_HOURS: u8 = 1;
+
+/// Default share of the overtime cap in percent from which on the stats warn
+/// about forfeiting overtime.
+pub const CAP_WARN_PERCENT: u8 = 90;
+
+/// Days with less work are considered accidental, e.g. a start immediately
+/// followed by a stop.
+pub const MIN_DAY_MINUTES: i64 = 5;
+
+impl Config {
+    /// This configuration with the defaults of unset settings filled in.
+    pub fn resolved(&self) -> Config {
+        Config {
+            weekly_stats: Some(self.weekly_stats.unwrap_or_default()),
+            split_over_midnight: Some(self.split_over_midnight.unwrap_or_default()),
+            initial_overtime_minutes: Some(self.initial_overtime_minutes.unwrap_or_default()),
+            backup_count: Some(self.backup_count.unwrap_or_default()),
+            warn_day_hours: Some(self.warn_day_hours.unwrap_or(WARN_DAY_HOURS)),
+            warn_break_hours: Some(self.warn_break_hours.unwrap_or(WARN_BREAK_HOURS)),
+            show_seconds: Some(self.show_seconds.unwrap_or_default()),
+            warn_idle_hours: Some(self.warn_idle_hours.unwrap_or(WARN_IDLE_HOURS)),
+            display_round_quarter: Some(self.display_round_quarter.unwrap_or_default()),
+            pretty_json: Some(self.pretty_json.unwrap_or_default()),
+            notifications: Some(self.notifications.unwrap_or_default()),
+            decimal_hours: Some(self.decimal_hours.unwrap_or_default()),
+            cap_warn_percent: Some(self.cap_warn_percent.unwrap_or(CAP_WARN_PERCENT)),
+            clock_skew_hours: Some(self.clock_skew_hours.unwrap_or(CLOCK_SKEW_HOURS)),
+            merge_same_day: Some(self.merges_same_day()),
+            ..self.clone()
+        }
+    }
+
+    /// Duration of work after which stopping warns about a long day.
+    pub fn warn_day(&self) -> Duration {
+        Duration::hours(self.warn_day_hours.unwrap_or(WARN_DAY_HOURS).into())
+    }
+
+    /// Duration of a break after which stopping it warns about a long break.
+    pub fn warn_break(&self) -> Duration {
+        Duration::hours(self.warn_break_hours.unwrap_or(WARN_BREAK_HOURS).into())
+    }
+
+    /// Duration since the start after which a forgotten stop is suspected.
+    pub fn warn_idle(&self) -> Duration {
+        Duration::hours(self.warn_idle_hours.unwrap_or(WARN_IDLE_HOURS).into())
+    }
+
+    /// Duration a start may lie before the stop of the latest entry.
+    pub fn clock_skew(&self) -> Duration {
+        Duration::hours(self.clock_skew_hours.unwrap_or(CLOCK_SKEW_HOURS).into())
+    }
+
+    /// Number of recent days the average start time is computed over, all
+    /// days if not set.
+    pub fn avg_window(&self) -> Option<u16> {
+        self.avg_window_days.filter(|d| *d > 0)
+    }
+
+    /// Whether entries of the same day and tags are merged, the default.
+    pub fn merges_same_day(&self) -> bool {
+        self.merge_same_day.unwrap_or(true)
+    }
+
+    /// Limit of the overtime in both directions, overtime beyond is forfeited.
+    pub fn overtime_cap(&self) -> Option<Duration> {
+        self.overtime_cap_hours.map(|h| Duration::hours(h.into()))
+    }
+
+    /// Time left until the positive overtime `hours` reach the overtime cap, if
+    /// they are beyond the configured share of it but not capped yet. Returns
+    /// the time left and the cap.
+    pub(crate) fn cap_left(&self, hours: Duration) -> Option<(Duration, Duration)> {
+        let cap = self.overtime_cap()?;
+        let percent = self.cap_warn_percent.unwrap_or(CAP_WARN_PERCENT);
+        let warn = cap * percent.into() / 100;
+        (hours >= warn && hours < cap).then(|| (cap - hours, cap))
+    }
+
+    /// First and last day of the pay period containing `day`, `None` if no
+    /// period start day is configured.
+    ///
+    /// Periods run from the start day to the day before it in the next month,
+    /// start days past the 28th are taken as the 28th to exist in every month.
+    pub fn pay_period(&self, day: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+        let start_day = self.period_start_day?.clamp(1, 28).into();
+        let start = if day.day() >= start_day {
+            day.with_day(start_day)?
+        } else {
+            day.with_day(1)?.pred_opt()?.with_day(start_day)?
+        };
+        let end = start
+            .checked_add_months(chrono::Months::new(1))?
+            .pred_opt()?;
+        Some((start, end))
+    }
+
+    /// Overtime of `worked` against the `daily` hours. Work on a day off
+    /// counts completely, differences within the daily grace period are
+    /// ignored and larger ones are reduced by it.
+    pub(crate) fn day_overtime(
+        &self,
+        day_off: bool,
+        worked: Duration,
+        daily: Duration,
+    ) -> Duration {
+        if day_off {
+            return worked;
+        }
+        let grace = Duration::minutes(self.daily_grace_minutes.unwrap_or_default().into());
+        let delta = worked - daily;
+        if delta > grace {
+            delta - grace
+        } else if delta < -grace {
+            delta + grace
+        } else {
+            Duration::zero()
+        }
+    }
+
+    /// Format `dur` as `HH:MMh`, `HH:MM:SSh` if seconds are shown or like
+    /// `7.50` if decimal hours are configured.
+    pub fn format_duration(&self, dur: Duration) -> String {
+        let dur = DurationDef::from(dur);
+        if self.decimal_hours.unwrap_or_default() {
+            dur.decimal()
+        } else if self.show_seconds.unwrap_or_default() {
+            format!("{:#}", dur)
+        } else {
+            dur.to_string()
+        }
+    }
+
+    /// Format `day` with the weekday and month names of the configured locale,
+    /// English if unset or unsupported.
+    pub fn format_date(&self, day: NaiveDate, fmt: &str) -> String {
+        #[cfg(feature = "locale")]
+        if let Some(locale) = self.chrono_locale() {
+            return day.format_localized(fmt, locale).to_string();
+        }
+        day.format(fmt).to_string()
+    }
+
+    /// Name of `month` in the configured locale.
+    pub fn month_name(&self, month: chrono::Month) -> String {
+        NaiveDate::from_ymd_opt(2000, month.number_from_month(), 1)
+            .map(|d| self.format_date(d, "%B"))
+            .unwrap_or_else(|| month.name().to_string())
+    }
+
+    /// The configured locale, `None` if unset or unknown to chrono.
+    #[cfg(feature = "locale")]
+    pub fn chrono_locale(&self) -> Option<chrono::Locale> {
+        let locale = self.locale.as_deref()?.replace('-', "_");
+        chrono::Locale::try_from(locale.as_str()).ok()
+    }
+
+    /// Earnings for working `dur` with the configured hourly rate, formatted
+    /// with the currency.
+    pub fn earnings(&self, dur: Duration) -> Option<String> {
+        let rate = self.hourly_rate?;
+        let amount = dur.num_seconds() as f64 / 3600.0 * rate;
+        Some(match &self.currency {
+            Some(c) => format!("{:.2} {}", amount, c),
+            None => format!("{:.2}", amount),
+        })
+    }
 }
 
 impl Default for Config {
@@ -120,17 +472,94 @@ impl Default for Config {
         Self {
             month_stats: 2,
             daily_hours: None,
+            weekly_hours: None,
             weekly_stats: None,
+            split_over_midnight: None,
+            initial_overtime_minutes: None,
+            holidays: Vec::new(),
+            vacations: Vec::new(),
+            backup_count: None,
+            backup_dir: None,
+            default_profile: None,
+            warn_day_hours: None,
+            warn_break_hours: None,
+            show_seconds: None,
+            warn_idle_hours: None,
+            hourly_rate: None,
+            currency: None,
+            display_round_quarter: None,
+            tracking_start_date: None,
+            pretty_json: None,
+            overtime_cap_hours: None,
+            daily_grace_minutes: None,
+            cap_warn_percent: None,
+            notifications: None,
+            locale: None,
+            decimal_hours: None,
+            auto_stop_at: None,
+            period_start_day: None,
+            clock_skew_hours: None,
+            avg_window_days: None,
+            merge_same_day: None,
         }
     }
 }
 
+/// Default configuration, referenced if a balance has no configuration.
+static DEFAULT_CONFIG: Config = Config {
+    month_stats: 2,
+    daily_hours: None,
+    weekly_hours: None,
+    weekly_stats: None,
+    split_over_midnight: None,
+    initial_overtime_minutes: None,
+    holidays: Vec::new(),
+    vacations: Vec::new(),
+    backup_count: None,
+    backup_dir: None,
+    default_profile: None,
+    warn_day_hours: None,
+    warn_break_hours: None,
+    show_seconds: None,
+    warn_idle_hours: None,
+    hourly_rate: None,
+    currency: None,
+    display_round_quarter: None,
+    tracking_start_date: None,
+    pretty_json: None,
+    overtime_cap_hours: None,
+    daily_grace_minutes: None,
+    cap_warn_percent: None,
+    notifications: None,
+    locale: None,
+    decimal_hours: None,
+    auto_stop_at: None,
+    period_start_day: None,
+    clock_skew_hours: None,
+    avg_window_days: None,
+    merge_same_day: None,
+};
+
 impl Default for &Config {
     fn default() -> Self {
-        &Config {
-            month_stats: 2,
-            daily_hours: None,
-            weekly_stats: None,
+        &DEFAULT_CONFIG
+    }
+}
+
+/// File formats the time balance can be stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StorageFormat {
+    Json,
+    Toml,
+}
+
+impl StorageFormat {
+    /// Choose the format by the extension of `path`, files not ending in
+    /// `.toml` are json.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Self::Toml,
+            _ => Self::Json,
         }
     }
 }
@@ -139,27 +568,39 @@ impl Default for &Config {
 /// completed breaks.
 ///
 /// Completed work sets are stored in a hash map with entries
-/// `(start, duration)`. If a break or work is running, the corresponding
-/// options hold the respective start time.
+/// `(stop, entry)` where the entry holds the duration and the breaks taken.
+/// If a break or work is running, the corresponding options hold the
+/// respective start time.
 #[derive(Debug, Deserialize, Serialize, PartialEq)]
 pub(crate) struct TimeBalance {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     start: Option<DateTime<Utc>>,
     breaking: Option<DateTime<Utc>>,
     breaks: Vec<(DateTime<Utc>, DurationDef)>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub config: Option<Config>,
     #[serde(rename = "account")]
-    time_account: BTreeMap<DateTime<Utc>, DurationDef>,
+    time_account: BTreeMap<DateTime<Utc>, WorkEntry>,
+    /// Manual changes of the overtime, e.g. time off taken from it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    adjustments: Vec<(DateTime<Utc>, DurationDef)>,
+    /// Days off like sick days, vacations and holidays.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    day_markers: BTreeMap<NaiveDate, DayKind>,
 }
 
 impl TimeBalance {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
+            name: None,
             time_account: BTreeMap::new(),
             start: None,
             breaking: None,
             config: None,
             breaks: Vec::new(),
+            adjustments: Vec::new(),
+            day_markers: BTreeMap::new(),
         }
     }
 
@@ -177,7 +618,7 @@ impl TimeBalance {
                 .map(|_| {
                     self.start = None;
                 })
-                .ok_or_else(|| eyre!(usage_err!("Nothing to cancel"))),
+                .ok_or_else(|| eyre!(TimeError::NothingToCancel)),
             Some(_) => {
                 self.breaking = None;
                 Ok(())
@@ -185,31 +626,249 @@ impl TimeBalance {
         }
     }
 
-    /// Add a start time to balance.
-    pub(crate) fn start(&mut self, time: DateTime<Utc>) -> Result<(), DateTime<Utc>> {
-        match self.start {
-            None => {
-                self.start = Some(time);
-                Ok(())
+    /// Record a manual change of the overtime by `dur` at `time`.
+    pub(crate) fn adjust_overtime(&mut self, time: DateTime<Utc>, dur: Duration) {
+        self.adjustments.push((time, dur.into()));
+    }
+
+    /// All manual changes of the overtime with the time they were recorded.
+    pub(crate) fn adjustments(&self) -> impl Iterator<Item = (DateTime<Utc>, Duration)> + '_ {
+        self.adjustments.iter().map(|(t, d)| (*t, d.into()))
+    }
+
+    /// Mark `day` as `kind`, returns the previous marker of the day.
+    pub(crate) fn mark_day(&mut self, day: NaiveDate, kind: DayKind) -> Option<DayKind> {
+        self.day_markers.insert(day, kind)
+    }
+
+    /// Marker of `day`, including the holidays and vacations kept in the
+    /// configuration by storages before day markers.
+    pub fn day_marker(&self, day: NaiveDate) -> Option<DayKind> {
+        self.day_markers.get(&day).copied().or_else(|| {
+            let cfg = self.config.as_ref()?;
+            if cfg.holidays.contains(&day) {
+                Some(DayKind::Holiday)
+            } else if cfg.vacations.contains(&day) {
+                Some(DayKind::Vacation)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Whether `day` is marked as day off, it counts as meeting the daily
+    /// working hours.
+    pub fn is_day_off(&self, day: NaiveDate) -> bool {
+        self.day_marker(day).is_some()
+    }
+
+    /// Marked days from `since` to `until`, both inclusive.
+    pub(crate) fn marked_days(
+        &self,
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Vec<(NaiveDate, DayKind)> {
+        since
+            .iter_days()
+            .take_while(|d| *d <= until)
+            .filter_map(|d| Some((d, self.day_marker(d)?)))
+            .collect()
+    }
+
+    /// Move the holidays and vacations of the configuration to the day
+    /// markers, returns the number of moved days.
+    pub(crate) fn migrate_days_off(&mut self) -> usize {
+        let Some(cfg) = self.config.as_mut() else {
+            return 0;
+        };
+        let days: Vec<(NaiveDate, DayKind)> = std::mem::take(&mut cfg.holidays)
+            .into_iter()
+            .map(|d| (d, DayKind::Holiday))
+            .chain(
+                std::mem::take(&mut cfg.vacations)
+                    .into_iter()
+                    .map(|d| (d, DayKind::Vacation)),
+            )
+            .collect();
+        for (day, kind) in &days {
+            self.day_markers.entry(*day).or_insert(*kind);
+        }
+        days.len()
+    }
+
+    /// Overtime of `worked` on `day` against the `daily` hours, see
+    /// `Config::day_overtime`, marked days count as day off.
+    pub(crate) fn day_overtime(
+        &self,
+        day: NaiveDate,
+        worked: Duration,
+        daily: Duration,
+    ) -> Duration {
+        self.config
+            .as_ref()
+            .unwrap_or_default()
+            .day_overtime(self.is_day_off(day), worked, daily)
+    }
+
+    /// Remove the started work together with its running and finished breaks.
+    pub(crate) fn cancel_all(&mut self) -> Result<()> {
+        if self.start.is_none() {
+            bail!(TimeError::NothingToCancel);
+        }
+        self.breaking = None;
+        self.reset();
+        Ok(())
+    }
+
+    /// Remove the finished break of the running work which started at the
+    /// local `time`, returns its duration.
+    pub(crate) fn cancel_break_at(&mut self, time: NaiveTime) -> Result<Duration> {
+        let hm = |t: &DateTime<Utc>| t.with_timezone(&Local).time().format("%H:%M").to_string();
+        let wanted = time.format("%H:%M").to_string();
+        match self.breaks.iter().position(|(s, _)| hm(s) == wanted) {
+            Some(i) => Ok(self.breaks.remove(i).1.into()),
+            None if self.breaks.is_empty() => {
+                bail!(usage_err!("There are no finished breaks to cancel"))
             }
-            Some(s) => Err(s),
+            None => bail!(usage_err!(
+                "There is no break at {}, breaks started at {}",
+                wanted,
+                self.breaks.iter().map(|(s, _)| hm(s)).join(", ")
+            )),
         }
     }
 
-    /// Stop the started time, calculate the duration by resolving all breaks
-    /// and the time since start.
-    pub(crate) fn stop(&mut self, time: DateTime<Utc>) -> Result<Duration> {
-        let start = self
-            .start
-            .ok_or_else(|| usage_err!("You did not start working"))?;
+    /// Add a forgotten break of `dur` starting at `start` to the finished work
+    /// around it, the work is reduced by the break. The break has to end before
+    /// the work stopped and must not overlap its other breaks. A break longer
+    /// than the work is cut to the work with `force`, rejected otherwise.
+    /// Returns the added break and the remaining work.
+    pub(crate) fn add_break(
+        &mut self,
+        start: DateTime<Utc>,
+        dur: Duration,
+        force: bool,
+    ) -> Result<(Duration, Duration)> {
+        if dur <= Duration::zero() {
+            bail!(usage_err!("A break needs to be longer than zero"));
+        }
+        let cfg = self.config.as_ref().unwrap_or_default();
+        let Some((stop, entry)) = self
+            .time_account
+            .iter_mut()
+            .find(|(stop, e)| e.start(**stop) <= start && start < **stop)
+        else {
+            bail!(usage_err!(
+                "There is no work at {} to add a break to",
+                start.with_timezone(&Local).format("%d.%m.%Y %H:%M")
+            ));
+        };
+        let worked = entry.duration.inner;
+        if dur > worked && !force {
+            bail!(usage_err!(
+                "The break of {} is longer than the {} worked, add it with --force anyway",
+                cfg.format_duration(dur),
+                cfg.format_duration(worked)
+            ));
+        }
+        let dur = dur.min(worked);
+        let end = start + dur;
+        if end > *stop {
+            bail!(usage_err!(
+                "The break ends at {}, after the work stopped at {}",
+                end.with_timezone(&Local).format("%H:%M"),
+                stop.with_timezone(&Local).format("%H:%M")
+            ));
+        }
+        if let Some((s, _)) = entry
+            .breaks
+            .iter()
+            .find(|(s, d)| *s < end && start < *s + Duration::from(d))
+        {
+            bail!(usage_err!(
+                "The break overlaps the break started at {}",
+                s.with_timezone(&Local).format("%H:%M")
+            ));
+        }
+        entry.duration = DurationDef::from(worked - dur);
+        entry.breaks.push((start, dur.into()));
+        entry.breaks.sort_by_key(|(s, _)| *s);
+        Ok((dur, entry.duration.inner))
+    }
+
+    /// Add a finished break of `dur` ending at `end` to the running work, e.g.
+    /// one taken without `break start`. Returns the start of the break.
+    pub(crate) fn add_running_break(
+        &mut self,
+        end: DateTime<Utc>,
+        dur: Duration,
+    ) -> Result<DateTime<Utc>> {
+        if dur <= Duration::zero() {
+            bail!(usage_err!("A break needs to be longer than zero"));
+        }
+        let start = self.start.ok_or(TimeError::NotStarted)?;
         if let Some(b) = self.breaking {
+            bail!(TimeError::AlreadyOnBreak(b));
+        }
+        let begin = end - dur;
+        if begin < start {
+            bail!(TimeError::BreakLongerThanWork);
+        }
+        if let Some((s, _)) = self
+            .breaks
+            .iter()
+            .find(|(s, d)| *s < end && begin < *s + Duration::from(d))
+        {
             bail!(usage_err!(
-                "You're on a break since {}, won't stop your current work.",
-                b.time().format("%H:%M")
+                "The break overlaps the break started at {}",
+                s.with_timezone(&Local).format("%H:%M")
             ));
         }
+        self.breaks.push((begin, dur.into()));
+        self.breaks.sort_by_key(|(s, _)| *s);
+        Ok(begin)
+    }
+
+    /// Add a start time to balance, `time` must not lie in the future of `now`.
+    pub(crate) fn start(&mut self, time: DateTime<Utc>, now: DateTime<Utc>) -> Result<()> {
+        if let Some(s) = self.start {
+            bail!(TimeError::AlreadyStarted(s));
+        }
+        ensure_not_future(time, now, "start")?;
+        self.start = Some(time);
+        Ok(())
+    }
+
+    /// Stop the started time, calculate the duration by resolving all breaks
+    /// and the time since start.
+    pub(crate) fn stop(&mut self, time: DateTime<Utc>, note: Option<String>) -> Result<Duration> {
+        self.stop_tagged(time, note, Vec::new(), false)
+    }
+
+    /// Stop like `stop` and tag the work with `tags`, blank ones are dropped.
+    /// An `explicit` time given by the user is used without asking if the work
+    /// started on an earlier day.
+    pub(crate) fn stop_tagged(
+        &mut self,
+        time: DateTime<Utc>,
+        note: Option<String>,
+        tags: Vec<String>,
+        explicit: bool,
+    ) -> Result<Duration> {
+        let start = self.start.ok_or(TimeError::NotStarted)?;
+        if let Some(since) = self.breaking {
+            bail!(TimeError::StopOnBreak { since, stop: time });
+        }
         let breaks = self.accumulate_breaks();
-        let stop = if start.naive_local().date() != time.naive_local().date() {
+        let split = self
+            .config
+            .as_ref()
+            .unwrap_or_default()
+            .split_over_midnight
+            .unwrap_or_default()
+            && start.with_timezone(&Local).date_naive() != time.with_timezone(&Local).date_naive();
+        let stop = if !split && !explicit && start.naive_local().date() != time.naive_local().date()
+        {
             println!(
                 "You started working on {}, do you really want to stop today? [y/N]",
                 start.format("%d.%m.")
@@ -226,15 +885,161 @@ impl TimeBalance {
         } else {
             time
         };
+        if stop < start {
+            bail!(TimeError::StopBeforeStart { start, stop });
+        }
+        let note = note.filter(|n| !n.trim().is_empty());
+        let mut tags: Vec<String> = tags
+            .iter()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        if split {
+            let duration = self.insert_split(start, stop, note, tags)?;
+            self.reset();
+            return Ok(duration);
+        }
         let duration = stop
             .signed_duration_since(start)
             .checked_sub(&breaks)
-            .ok_or_else(|| usage_err!("Your break was longer than your work"))?;
-        self.insert(stop, duration.into());
+            .ok_or(TimeError::BreakLongerThanWork)?;
+        let entry = WorkEntry {
+            duration: duration.into(),
+            breaks: self.breaks.clone(),
+            note,
+            tags,
+        };
+        self.insert(stop, entry);
         self.reset();
         Ok(duration)
     }
 
+    /// Stop the started work after `dur` of work instead of at a point in
+    /// time, the stop is derived from the start and must not lie after `now`.
+    /// The recorded breaks are subtracted from `dur` unless `gross` is set.
+    pub(crate) fn stop_after(
+        &mut self,
+        dur: Duration,
+        gross: bool,
+        note: Option<String>,
+        tags: Vec<String>,
+        now: DateTime<Utc>,
+    ) -> Result<Duration> {
+        let start = self.start.ok_or(TimeError::NotStarted)?;
+        let breaks = self.accumulate_breaks();
+        if !gross && dur < breaks {
+            bail!(usage_err!(
+                "Your breaks of {} are longer than the work, stop with '--gross' to not subtract them",
+                self.config
+                    .as_ref()
+                    .unwrap_or_default()
+                    .format_duration(breaks)
+            ));
+        }
+        let stop = if gross {
+            start + dur + breaks
+        } else {
+            start + dur
+        };
+        if stop > now {
+            bail!(TimeError::InFuture {
+                action: "stop",
+                time: stop
+            });
+        }
+        self.stop_tagged(stop, note, tags, true)
+    }
+
+    /// Split the work from `start` to `stop` at local midnight and insert one
+    /// entry per day.
+    ///
+    /// Like every entry, each part is keyed by its stop, i.e. midnight for all
+    /// but the last one. Breaks over midnight are split likewise, the `note`
+    /// goes to the last day and the `tags` to every day.
+    fn insert_split(
+        &mut self,
+        start: DateTime<Utc>,
+        stop: DateTime<Utc>,
+        note: Option<String>,
+        tags: Vec<String>,
+    ) -> Result<Duration> {
+        let mut entries = Vec::new();
+        let mut begin = start;
+        while begin < stop {
+            let midnight = begin
+                .with_timezone(&Local)
+                .date_naive()
+                .succ_opt()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .and_then(|d| d.and_local_timezone(Local).earliest())
+                .ok_or(eyre!("Failed to find midnight after {}", begin))?
+                .with_timezone(&Utc);
+            let end = midnight.min(stop);
+            let breaks: Vec<(DateTime<Utc>, DurationDef)> = self
+                .breaks
+                .iter()
+                .filter_map(|(s, d)| {
+                    let (from, to) = ((*s).max(begin), (*s + Duration::from(d)).min(end));
+                    (from < to).then(|| (from, DurationDef::from(to - from)))
+                })
+                .collect();
+            let break_sum = breaks
+                .iter()
+                .fold(Duration::zero(), |acc, (_, d)| acc + d.into());
+            let duration = end
+                .signed_duration_since(begin)
+                .checked_sub(&break_sum)
+                .filter(|d| *d >= Duration::zero())
+                .ok_or(TimeError::BreakLongerThanWork)?;
+            log::trace!("Split entry at {}: {:?}", end, duration);
+            entries.push((
+                end,
+                WorkEntry {
+                    duration: duration.into(),
+                    breaks,
+                    note: None,
+                    tags: tags.clone(),
+                },
+            ));
+            begin = end;
+        }
+        if let Some((_, last)) = entries.last_mut() {
+            last.note = note;
+        }
+
+        let mut total = Duration::zero();
+        for (key, entry) in entries {
+            total += Duration::from(&entry);
+            self.insert(key, entry);
+        }
+        Ok(total)
+    }
+
+    /// Remove the most recent entry and start again as if it was never stopped.
+    ///
+    /// The start is derived from the stop, duration and breaks of the entry,
+    /// its breaks become the breaks of the running work again. Returns the
+    /// duration already worked.
+    pub(crate) fn continue_last(&mut self) -> Result<Duration> {
+        if let Some(s) = self.start {
+            bail!(TimeError::AlreadyStarted(s));
+        }
+        let (stop, entry) = self
+            .time_account
+            .pop_last()
+            .ok_or_else(|| usage_err!("There is nothing to continue"))?;
+        let duration = Duration::from(&entry);
+        let breaks = entry
+            .breaks
+            .iter()
+            .fold(Duration::zero(), |acc, (_, d)| acc + d.into());
+        self.start = Some(stop - duration - breaks);
+        self.breaks = entry.breaks;
+        Ok(duration)
+    }
+
     /// Sum up duration of all finished breaks.
     pub(crate) fn accumulate_breaks(&self) -> Duration {
         self.breaks
@@ -242,37 +1047,57 @@ impl TimeBalance {
             .fold(Duration::seconds(0), |acc, b| acc + b.1.into())
     }
 
+    /// Statistics of all finished breaks, those of the stopped work and of the
+    /// running work. `None` if there are none.
+    pub(crate) fn break_stats(&self) -> Option<BreakStats> {
+        let (count, total) = self
+            .time_account
+            .values()
+            .flat_map(|e| e.breaks.iter())
+            .chain(self.breaks.iter())
+            .fold((0, Duration::zero()), |(n, sum), (_, d)| {
+                (n + 1, sum + d.inner)
+            });
+        (count > 0).then(|| BreakStats {
+            count,
+            total,
+            average: total / count as i32,
+        })
+    }
+
     /// Get all breaks.
     pub(crate) fn get_breaks(&self) -> Vec<(DateTime<Utc>, Duration)> {
         self.breaks.iter().map(|(s, d)| (*s, d.into())).collect()
     }
 
-    /// Add `time` as start of break.
-    pub(crate) fn start_break(&mut self, time: DateTime<Utc>) -> Result<Duration> {
-        self.start
-            .ok_or_else(|| {
-                eyre!(usage_err!(
-                    "You're not tracking your work so you can't take a break"
-                ))
-            })
-            .map(|s| {
-                if self.breaking.is_none() {
-                    self.breaking = Some(time);
-                    Some(time.signed_duration_since(s))
-                } else {
-                    None
-                }
-            })?
-            .ok_or_else(|| eyre!(usage_err!("You're already on a break")))
+    /// Add `time` as start of break, it must not lie in the future of `now`.
+    pub(crate) fn start_break(
+        &mut self,
+        time: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<Duration> {
+        ensure_not_future(time, now, "start a break")?;
+        let start = self.start.ok_or(TimeError::NotStarted)?;
+        if let Some(b) = self.breaking {
+            bail!(TimeError::AlreadyOnBreak(b));
+        }
+        self.breaking = Some(time);
+        Ok(time.signed_duration_since(start))
     }
 
     /// Calculate duration of current break.
     pub(crate) fn finish_break(&mut self, time: DateTime<Utc>) -> Result<Duration> {
-        self.start
-            .ok_or_else(|| usage_err!("You can't break if you haven't started."))?;
+        self.start.ok_or(TimeError::NotStarted)?;
         let break_start = self
             .breaking
-            .ok_or_else(|| usage_err!("You're not on a break right now."))?;
+            .ok_or_else(|| TimeError::NotOnBreak(self.get_breaks().last().copied()))?;
+        if time < break_start {
+            bail!(usage_err!(
+                "Break end {} is before its start {}",
+                time.with_timezone(&Local).time().format("%H:%M"),
+                break_start.with_timezone(&Local).time().format("%H:%M")
+            ));
+        }
 
         let dur = time.signed_duration_since(break_start);
         self.breaks.push((break_start, dur.into()));
@@ -281,59 +1106,32 @@ impl TimeBalance {
         Ok(dur)
     }
 
-    /// Extract all entries in map between two time points.
+    /// Extract all entries in map between two time points, none if `upper`
+    /// lies before `lower`.
     fn range(
         &self,
         lower: DateTime<Utc>,
         upper: DateTime<Utc>,
-    ) -> impl Iterator<Item = (&DateTime<Utc>, &DurationDef)> {
-        let range = lower..upper;
+    ) -> impl Iterator<Item = (&DateTime<Utc>, &WorkEntry)> {
+        let range = lower..upper.max(lower);
         log::trace!("{:?} in {:?}", &range, &self.time_account);
         self.time_account.range(range)
     }
 
     /// Extract all entries from within one month.
+    ///
+    /// Entries are keyed by their stop in local time, so work past midnight
+    /// into the next month counts for the next month unless
+    /// `split_over_midnight` is configured.
     pub fn month_range(
         &self,
         year: i32,
         month: Month,
-    ) -> Result<impl Iterator<Item = (&DateTime<Utc>, &DurationDef)>> {
+    ) -> Result<impl Iterator<Item = (&DateTime<Utc>, &WorkEntry)>> {
         log::trace!("Range for month {:?}", month);
-        let current = Utc
-            .with_ymd_and_hms(year, month.number_from_month(), 1, 0, 0, 0)
-            .latest()
-            .ok_or(eyre!("Could not construct range"))?;
-        let days_in_m = if month.number_from_month() == 12 {
-            Utc.with_ymd_and_hms(year + 1, month.succ().number_from_month(), 1, 0, 0, 0)
-                .earliest()
-                .ok_or(eyre!("Could not construct range"))?
-                .signed_duration_since(current)
-                .num_days()
-        } else {
-            Utc.with_ymd_and_hms(year, month.succ().number_from_month(), 1, 0, 0, 0)
-                .earliest()
-                .ok_or(eyre!("Could not construct range"))?
-                .signed_duration_since(current)
-                .num_days()
-        };
-        log::trace!("Days in month {:?}: {}", month, days_in_m);
-        let lower = Utc
-            .with_ymd_and_hms(year, month.number_from_month(), 1, 0, 0, 0)
-            .earliest()
-            .ok_or(eyre!("Could not create range"))?;
-        let upper = Utc
-            .with_ymd_and_hms(
-                year,
-                month.number_from_month(),
-                days_in_m as u32,
-                23,
-                59,
-                59,
-            )
-            .latest()
-            .ok_or(eyre!("Could not create range"))?;
-        log::trace!("Lower: {:?}, Upper: {:?}", lower, upper);
-        Ok(self.range(lower, upper))
+        let (first, last) = month_bounds(year, month)?;
+        log::trace!("First: {:?}, last: {:?}", first, last);
+        self.dates_range(first, last, Local)
     }
 
     /// Extract all entries from one day.
@@ -341,77 +1139,208 @@ impl TimeBalance {
         &self,
         day: NaiveDate,
         tz: T,
-    ) -> Result<impl Iterator<Item = (&DateTime<Utc>, &DurationDef)>> {
+    ) -> Result<impl Iterator<Item = (&DateTime<Utc>, &WorkEntry)>> {
         log::trace!("Entries for {:?}", day);
-        let start = day
+        self.dates_range(day, day, tz)
+    }
+
+    /// Extract all entries stopped from the day `since` to the day `until`,
+    /// both inclusive, in timezone `tz`.
+    pub fn dates_range<T: chrono::offset::TimeZone>(
+        &self,
+        since: NaiveDate,
+        until: NaiveDate,
+        tz: T,
+    ) -> Result<impl Iterator<Item = (&DateTime<Utc>, &WorkEntry)>> {
+        let start = since
             .and_hms_opt(0, 0, 0)
             .ok_or(eyre!("Could not construct range"))?
             .and_local_timezone(tz.clone())
             .earliest()
             .ok_or(eyre!("Could not construct range"))?
             .with_timezone(&Utc);
-        let end = day
-            .and_hms_opt(23, 59, 59)
+        let end = until
+            .succ_opt()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
             .ok_or(eyre!("Could not construct range"))?
             .and_local_timezone(tz)
-            .latest()
+            .earliest()
             .ok_or(eyre!("Could not construct range"))?
             .with_timezone(&Utc);
-        Ok(self.range(start, end))
+        // Work stopped at midnight belongs to the day before, see `entry_day`.
+        Ok(self
+            .time_account
+            .range((Bound::Excluded(start), Bound::Included(end.max(start)))))
     }
 
     /// Extract all entries from the week of `date`.
     pub fn week_entries(
         &self,
         day: NaiveDate,
-    ) -> impl Iterator<Item = (&DateTime<Utc>, &DurationDef)> {
-        let week = day.iso_week().week();
-        let (_, year) = day.year_ce();
-        log::trace!("Entries in week {}, based on day {:?}", week, day);
+    ) -> impl Iterator<Item = (&DateTime<Utc>, &WorkEntry)> {
+        let week = day.iso_week();
+        log::trace!("Entries in week {:?}, based on day {:?}", week, day);
         self.time_account
             .iter()
-            .filter(move |(d, _)| d.year() == year as i32)
-            .filter(move |(d, _)| d.iso_week().week() == week)
+            .filter(move |(d, _)| entry_day(d).iso_week() == week)
+    }
+
+    /// Get all finished breaks of one day, including breaks of the running work.
+    pub fn breaks_on<T: chrono::offset::TimeZone>(
+        &self,
+        day: NaiveDate,
+        tz: T,
+    ) -> Result<Vec<(DateTime<Utc>, Duration)>> {
+        let mut breaks: Vec<(DateTime<Utc>, Duration)> = self
+            .daily_range(day, tz.clone())?
+            .flat_map(|(_, e)| e.breaks.iter().map(|(s, d)| (*s, d.into())))
+            .collect();
+        breaks.extend(
+            self.get_breaks()
+                .into_iter()
+                .filter(|(s, _)| s.with_timezone(&tz).date_naive() == day),
+        );
+        Ok(breaks)
     }
 
-    /// Insert a start time and the corresponding duration into map.
-    pub(crate) fn insert(&mut self, dt: DateTime<Utc>, dur: DurationDef) {
-        self.time_account.insert(dt, dur);
+    /// Insert a stop time and the corresponding entry into map.
+    pub(crate) fn insert(&mut self, dt: DateTime<Utc>, entry: WorkEntry) {
+        self.time_account.insert(dt, entry);
     }
 
-    /// Deserialize json buffer.
-    fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
-        serde_json::from_reader(reader).wrap_err(
-            "Failed to deserialize json. Try 'stempel migrate' to migrate to new json format",
-        )
+    /// Deserialize json or toml buffer.
+    fn from_reader<R: Read>(reader: &mut R, format: StorageFormat) -> Result<Self> {
+        match format {
+            StorageFormat::Json => serde_json::from_reader(reader).wrap_err(
+                "Failed to deserialize json. Try 'stempel migrate' to migrate to new json format",
+            ),
+            #[cfg(feature = "toml")]
+            StorageFormat::Toml => {
+                let mut buf = String::new();
+                reader
+                    .read_to_string(&mut buf)
+                    .wrap_err("Failed to read toml")?;
+                toml::from_str(&buf).wrap_err("Failed to deserialize toml")
+            }
+            #[cfg(not(feature = "toml"))]
+            StorageFormat::Toml => Err(feature_disabled("toml").into()),
+        }
     }
 
-    /// Serialize time balance to json.
-    fn write<W>(&self, writer: &mut W) -> Result<()>
+    /// Serialize time balance to json or toml, json is indented if configured.
+    fn write<W>(&self, writer: &mut W, format: StorageFormat) -> Result<()>
     where
         W: Write,
     {
-        serde_json::to_writer(writer, &self).wrap_err("Failed to serialize to json")
+        let pretty = self
+            .config
+            .as_ref()
+            .unwrap_or_default()
+            .pretty_json
+            .unwrap_or_default();
+        match format {
+            StorageFormat::Json if pretty => {
+                serde_json::to_writer_pretty(writer, &self).wrap_err("Failed to serialize to json")
+            }
+            StorageFormat::Json => {
+                serde_json::to_writer(writer, &self).wrap_err("Failed to serialize to json")
+            }
+            #[cfg(feature = "toml")]
+            StorageFormat::Toml => {
+                let buf = toml::to_string(&self).wrap_err("Failed to serialize to toml")?;
+                writer
+                    .write_all(buf.as_bytes())
+                    .wrap_err("Failed to write toml")
+            }
+            #[cfg(not(feature = "toml"))]
+            StorageFormat::Toml => Err(feature_disabled("toml").into()),
+        }
     }
 
-    /// Read from json file.
+    /// Read from json or toml file, depending on the file extension.
+    ///
+    /// A missing file results in an empty balance if `create` is set, an
+    /// error pointing to `stempel init` otherwise.
     pub fn from_file<P: AsRef<Path>>(path: P, create: bool) -> Result<Self> {
-        match File::open(&path) {
+        let format = StorageFormat::from_path(&path);
+        let balance = match File::open(&path) {
             Ok(f) => {
                 let mut reader = BufReader::new(f);
-                let s = Self::from_reader(&mut reader)?;
-                Ok(s)
+                Self::from_reader(&mut reader, format).map_err(|e| {
+                    match e.downcast_ref::<serde_json::Error>() {
+                        Some(j) if j.is_syntax() || j.is_eof() => {
+                            let hint = match crate::backup::latest(&path, None) {
+                                Some(b) => format!(
+                                    "Run 'stempel restore' to restore it from '{}'.",
+                                    b.display()
+                                ),
+                                None => "There is no backup to restore it from.".to_string(),
+                            };
+                            eyre!(TimeError::StorageCorrupt {
+                                path: path.as_ref().to_path_buf(),
+                                reason: j.to_string(),
+                                hint,
+                            })
+                        }
+                        _ if Self::read_legacy(&path).is_some() => {
+                            eyre!(TimeError::LegacyStorage(path.as_ref().to_path_buf()))
+                        }
+                        _ => e,
+                    }
+                })
             }
             Err(_) if create => Ok(TimeBalance::new()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(eyre!(
+                TimeError::StorageMissing(path.as_ref().to_path_buf())
+            )),
             Err(e) => Err(e)
                 .wrap_err_with(|| format!("Failed to open storage '{}'", path.as_ref().display())),
-        }
+        }?;
+        Ok(balance)
     }
 
-    /// Write time balance to json file.
+    /// Read like `from_file`, a json storage in the legacy format is converted
+    /// in memory without migrating the file. Only for commands which never
+    /// write the storage.
+    pub fn from_file_or_legacy<P: AsRef<Path>>(path: P, create: bool) -> Result<Self> {
+        match Self::from_file(&path, create) {
+            Err(e) => match Self::read_legacy(&path) {
+                Some(legacy) => {
+                    log::warn!(
+                        "Storage '{}' is in the legacy format, run 'stempel migrate' to convert it.",
+                        path.as_ref().display()
+                    );
+                    Self::try_from(&legacy)
+                }
+                None => Err(e),
+            },
+            result => result,
+        }
+    }
+
+    /// Read the json storage at `path` in the legacy format, `None` if it's
+    /// missing or in another format.
+    fn read_legacy<P: AsRef<Path>>(path: P) -> Option<WorkStorage> {
+        if StorageFormat::from_path(&path) != StorageFormat::Json {
+            return None;
+        }
+        let reader = BufReader::new(File::open(path).ok()?);
+        serde_json::from_reader(reader).ok()
+    }
+
+    /// Write time balance to json or toml file, depending on the file extension.
+    ///
+    /// Rotates the configured number of backups before overwriting the file.
     pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let format = StorageFormat::from_path(&path);
+        let cfg = self.config.as_ref().unwrap_or_default();
+        crate::backup::rotate(
+            &path,
+            cfg.backup_count.unwrap_or_default(),
+            cfg.backup_dir.as_deref(),
+        )?;
         match OpenOptions::new().write(true).truncate(true).open(&path) {
-            Ok(mut f) => self.write(&mut f),
+            Ok(mut f) => self.write(&mut f, format),
             Err(_) => {
                 log::info!("Creating a new storage file {}", path.as_ref().display());
                 let mut f = File::create(&path).wrap_err_with(|| {
@@ -420,23 +1349,48 @@ impl TimeBalance {
                         path.as_ref().display()
                     )
                 })?;
-                self.write(&mut f)
+                self.write(&mut f, format)
             }
         }
     }
 
-    /// Get start point and duration since then. None if there is no start entry.
-    pub fn start_state(&self) -> Option<(Duration, DateTime<Utc>)> {
+    /// Get start point and duration since then until `now`. None if there is
+    /// no start entry.
+    pub fn start_state(&self, now: DateTime<Utc>) -> Option<(Duration, DateTime<Utc>)> {
         if let Some(s) = self.start {
-            let dur = Utc::now().signed_duration_since(s);
+            let dur = now.signed_duration_since(s);
             Some((dur, s))
         } else {
             None
         }
     }
 
-    /// Get start and duration of break if any
-    pub fn break_state(&self) -> BreakeState {
+    /// The configured auto stop time on the day of the running start if `now`
+    /// is past it.
+    pub(crate) fn overdue_auto_stop(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let at = self.config.as_ref()?.auto_stop_at?;
+        let start = self.start?;
+        let stop = start
+            .with_timezone(&Local)
+            .date_naive()
+            .and_time(at)
+            .and_local_timezone(Local)
+            .earliest()?
+            .with_timezone(&Utc);
+        (stop > start && stop <= now).then_some(stop)
+    }
+
+    /// Time from the start until `now` if it exceeds the configured idle
+    /// threshold.
+    pub fn idle(&self, now: DateTime<Utc>) -> Option<Duration> {
+        let (dur, _) = self.start_state(now)?;
+        let cfg = self.config.as_ref().unwrap_or_default();
+        (dur > cfg.warn_idle()).then_some(dur)
+    }
+
+    /// Get start and duration of break if any, a running break lasts until
+    /// `now`.
+    pub fn break_state(&self, now: DateTime<Utc>) -> BreakeState {
         let break_sum = self.accumulate_breaks();
         if self.start.is_none() {
             return BreakeState {
@@ -446,8 +1400,8 @@ impl TimeBalance {
             };
         }
         let current = self.breaking;
-        let sum = Utc::now()
-            .signed_duration_since(current.unwrap_or_else(Utc::now))
+        let sum = now
+            .signed_duration_since(current.unwrap_or(now))
             .checked_add(&break_sum)
             .unwrap_or(break_sum);
         BreakeState {
@@ -457,55 +1411,286 @@ impl TimeBalance {
         }
     }
 
-    /// Merge entries of the same day in the storage.
+    /// Merge entries of the same local day and tags in the storage.
+    ///
+    /// The entries of one day are merged in order into one entry keyed by the
+    /// last stop of the day. Nothing is merged if `merge_same_day` is disabled.
     pub fn canocicalize(&mut self) -> Result<()> {
-        let mut current = self.time_account.iter();
-        let mut peek = current.clone().skip(1).peekable();
-        let mut merge = Vec::new();
-        while let (Some(ne), Some(cur)) = (peek.peek(), current.next()) {
-            if ne.0.date_naive() == cur.0.date_naive() {
-                merge.push((*cur.0, *ne.0));
+        for keys in self.same_day_keys() {
+            log::trace!("Merging keys {}: {:?}", keys.len(), keys);
+            let mut merged: Option<WorkEntry> = None;
+            for k in &keys {
+                let entry = self
+                    .time_account
+                    .remove(k)
+                    .ok_or(eyre!("Failed to remove duplicate element"))?;
+                match merged.as_mut() {
+                    Some(m) => m.merge(entry),
+                    None => merged = Some(entry),
+                }
             }
-            peek.next();
+            let last = keys.last().ok_or(eyre!("Failed to update element"))?;
+            self.time_account
+                .insert(*last, merged.ok_or(eyre!("Failed to update element"))?);
         }
 
-        log::trace!("Removing keys {}: {:?}", merge.len(), merge);
-        for (del_k, mer_k) in merge {
-            let added = self
-                .time_account
-                .remove(&del_k)
-                .ok_or(eyre!("Failed to remove duplicate element"))?;
-            let cur = self
-                .time_account
-                .get(&mer_k)
-                .ok_or(eyre!("Failed to update element"))?;
-            log::trace!("Adding {:?} to {:?}", added, cur);
-            *self
-                .time_account
-                .get_mut(&mer_k)
-                .ok_or(eyre!("Failed to canocicalize"))? = *cur + added;
+        Ok(())
+    }
+
+    /// Days with more than one entry and their number of entries, i.e. the
+    /// merges `canocicalize` would do.
+    pub(crate) fn planned_merges(&self) -> Vec<(NaiveDate, usize)> {
+        self.same_day_keys()
+            .iter()
+            .map(|keys| (entry_day(&keys[0]), keys.len()))
+            .collect()
+    }
+
+    /// Keys of the entries sharing their local day and tags with other
+    /// entries, grouped by day and tags. None if merging them is disabled.
+    fn same_day_keys(&self) -> Vec<Vec<DateTime<Utc>>> {
+        let mut same_day = Vec::new();
+        if !self.config.as_ref().unwrap_or_default().merges_same_day() {
+            return same_day;
+        }
+        for (_, day) in &self.time_account.iter().group_by(|(k, _)| entry_day(k)) {
+            let mut by_tags: Vec<(&[String], Vec<DateTime<Utc>>)> = Vec::new();
+            for (k, e) in day {
+                match by_tags.iter_mut().find(|(t, _)| *t == e.tags.as_slice()) {
+                    Some((_, keys)) => keys.push(*k),
+                    None => by_tags.push((&e.tags, vec![*k])),
+                }
+            }
+            same_day.extend(
+                by_tags
+                    .into_iter()
+                    .map(|(_, keys)| keys)
+                    .filter(|keys| keys.len() > 1),
+            );
         }
+        same_day
+    }
 
-        Ok(())
+    /// Keep only the entries carrying one of `tags`, and the untagged ones if
+    /// `untagged` is set.
+    pub(crate) fn retain_tagged(&mut self, tags: &[String], untagged: bool) {
+        self.time_account.retain(|_, e| {
+            if e.tags.is_empty() {
+                untagged
+            } else {
+                e.tags.iter().any(|t| tags.contains(t))
+            }
+        });
+    }
+
+    /// Stop of the latest entry if `start` lies more than the configured clock
+    /// skew before it, e.g. because the clock went backwards.
+    pub(crate) fn skewed_start(&self, start: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let latest = *self.time_account.keys().next_back()?;
+        let skew = self.config.as_ref().unwrap_or_default().clock_skew();
+        (latest - start > skew).then_some(latest)
+    }
+
+    /// Find entries which are implausible, e.g. after a faulty migration.
+    pub(crate) fn anomalies(&self, now: DateTime<Utc>) -> Vec<(DateTime<Utc>, Anomaly)> {
+        let mut anomalies = Vec::new();
+        let same_day: Vec<DateTime<Utc>> = self
+            .same_day_keys()
+            .into_iter()
+            .flat_map(|keys| keys.into_iter().skip(1))
+            .collect();
+        let skew = self.config.as_ref().unwrap_or_default().clock_skew();
+        let mut previous: Option<DateTime<Utc>> = None;
+        for (k, e) in &self.time_account {
+            let dur = Duration::from(e);
+            if dur < Duration::zero() {
+                anomalies.push((*k, Anomaly::Negative));
+            } else if dur.is_zero() {
+                anomalies.push((*k, Anomaly::Empty));
+            } else if dur > Duration::hours(24) {
+                anomalies.push((*k, Anomaly::TooLong));
+            } else if previous.is_some_and(|p| p - e.start(*k) > skew) {
+                anomalies.push((*k, Anomaly::ClockSkew));
+            }
+            previous = Some(*k);
+            if *k > now + Duration::seconds(MAX_FUTURE_SECS) {
+                anomalies.push((*k, Anomaly::Future));
+            }
+            if same_day.contains(k) {
+                anomalies.push((*k, Anomaly::SameDay));
+            }
+        }
+        anomalies
+    }
+
+    /// Drop impossible entries, i.e. empty, negative or future ones, and merge
+    /// entries of the same day. Returns the number of dropped entries.
+    pub(crate) fn fix_anomalies(&mut self, now: DateTime<Utc>) -> Result<usize> {
+        let dropped: Vec<DateTime<Utc>> = self
+            .anomalies(now)
+            .into_iter()
+            .filter(|(_, a)| a.is_impossible())
+            .map(|(k, _)| k)
+            .dedup()
+            .collect();
+        for k in &dropped {
+            self.time_account.remove(k);
+        }
+        self.canocicalize()?;
+        Ok(dropped.len())
+    }
+
+    /// Sum up all tracked work.
+    pub fn total_worked(&self) -> Duration {
+        self.time_account
+            .values()
+            .fold(Duration::zero(), |acc, e| acc + e.into())
+    }
+
+    /// Local date of the earliest entry, if any.
+    pub fn first_day(&self) -> Option<NaiveDate> {
+        self.time_account.keys().next().map(entry_day)
+    }
+
+    /// Local date of the latest entry, if any.
+    pub fn last_day(&self) -> Option<NaiveDate> {
+        self.time_account.keys().next_back().map(entry_day)
+    }
+
+    /// Average wall clock time in timezone `tz` of the first start of each
+    /// day stopped before `now`, within the configured window of recent days
+    /// if set.
+    ///
+    /// The times of day are averaged after converting each start to `tz`, so
+    /// the offset can't shift the average across midnight.
+    pub(crate) fn avg_start_time<T: TimeZone>(
+        &self,
+        now: DateTime<Utc>,
+        tz: T,
+    ) -> Option<NaiveTime> {
+        let lower = match self.config.as_ref().unwrap_or_default().avg_window() {
+            Some(days) => now - Duration::days(days.into()),
+            None => DateTime::<Utc>::MIN_UTC,
+        };
+        let starts: Vec<i64> = self
+            .range(lower, now)
+            .map(|(k, e)| e.start(*k).with_timezone(&tz))
+            .group_by(|s| s.date_naive())
+            .into_iter()
+            .filter_map(|(_, group)| group.min())
+            .map(|s| s.time().num_seconds_from_midnight().into())
+            .collect();
+        if starts.is_empty() {
+            return None;
+        }
+        let avg = starts.iter().sum::<i64>() / starts.len() as i64;
+        NaiveTime::from_num_seconds_from_midnight_opt(avg as u32, 0)
+    }
+
+    /// Shortest and longest local day of work with their dates, entries of
+    /// the same day are summed up as if the balance was canocicalized.
+    ///
+    /// Days with less than `MIN_DAY_MINUTES` of work are considered accidental
+    /// and ignored.
+    pub fn extremes(&self) -> Option<((NaiveDate, Duration), (NaiveDate, Duration))> {
+        let days: Vec<(NaiveDate, Duration)> = self
+            .time_account
+            .iter()
+            .map(|(k, v)| (entry_day(k), Duration::from(v)))
+            .group_by(|(day, _)| *day)
+            .into_iter()
+            .map(|(day, group)| (day, group.fold(Duration::zero(), |acc, (_, d)| acc + d)))
+            .filter(|(_, d)| *d >= Duration::minutes(MIN_DAY_MINUTES))
+            .collect();
+        let min = days.iter().min_by_key(|(_, d)| *d)?;
+        let max = days.iter().max_by_key(|(_, d)| *d)?;
+        Some((*min, *max))
+    }
+
+    /// Count the distinct local days with tracked work, entries of the same
+    /// day are counted once just as if the balance was canocicalized.
+    pub fn worked_days(&self) -> usize {
+        self.time_account.keys().map(entry_day).dedup().count()
     }
 
-    /// Calculate total overhours.
+    /// Calculate total overhours, starting from the configured initial overtime.
+    ///
+    /// Work on holidays and vacations counts completely as overtime, work
+    /// before the configured tracking start date is ignored. Each day is
+    /// compared to the daily hours once, see `TimeBalance::day_overtime`. Manual
+    /// adjustments are added and the total is clamped to the configured
+    /// overtime cap.
     pub fn calculate_overhours(&self) -> Option<Duration> {
-        if let Some(daily) = self.config.as_ref().unwrap_or_default().daily_hours {
+        let cfg = self.config.as_ref().unwrap_or_default();
+        if let Some(daily) = cfg.daily_hours {
             let daily = Duration::hours(daily as i64);
+            let initial = self
+                .adjustments()
+                .fold(Duration::zero(), |acc, (_, d)| acc + d)
+                + Duration::minutes(cfg.initial_overtime_minutes.unwrap_or_default());
             let hours = self
                 .time_account
                 .iter()
-                .fold(Duration::zero(), |mut acc, (_, v)| {
-                    let dur: Duration = v.into();
-                    acc = acc + dur - daily;
-                    acc
+                .map(|(k, v)| (entry_day(k), Duration::from(v)))
+                .filter(|(day, _)| cfg.tracking_start_date.is_none_or(|s| *day >= s))
+                .group_by(|(day, _)| *day)
+                .into_iter()
+                .fold(initial, |acc, (day, group)| {
+                    let worked = group.fold(Duration::zero(), |acc, (_, d)| acc + d);
+                    acc + self.day_overtime(day, worked, daily)
                 });
-            Some(hours)
+            Some(match cfg.overtime_cap() {
+                Some(cap) => hours.clamp(-cap, cap),
+                None => hours,
+            })
         } else {
             None
         }
     }
+
+    /// Overtime within one `month`, only worked days count against the daily
+    /// hours. `None` if no daily hours are configured.
+    ///
+    /// Like `calculate_overhours`, work on days off counts completely, the
+    /// grace period applies per day and work before the tracking start date
+    /// is ignored.
+    pub fn overhours_for_month(&self, year: i32, month: Month) -> Result<Option<Duration>> {
+        let (first, last) = month_bounds(year, month)?;
+        self.overhours_for_range(first, last)
+    }
+
+    /// Overtime from the day `since` to the day `until`, both inclusive, like
+    /// `overhours_for_month`, e.g. of a pay period.
+    pub fn overhours_for_range(
+        &self,
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<Option<Duration>> {
+        let cfg = self.config.as_ref().unwrap_or_default();
+        let Some(daily) = cfg.daily_hours else {
+            return Ok(None);
+        };
+        let daily = Duration::hours(daily as i64);
+        let hours = self
+            .dates_range(since, until, Local)?
+            .map(|(k, v)| (entry_day(k), Duration::from(v)))
+            .filter(|(day, _)| cfg.tracking_start_date.is_none_or(|s| *day >= s))
+            .group_by(|(day, _)| *day)
+            .into_iter()
+            .fold(Duration::zero(), |acc, (day, group)| {
+                let worked = group.fold(Duration::zero(), |acc, (_, d)| acc + d);
+                acc + self.day_overtime(day, worked, daily)
+            });
+        Ok(Some(hours))
+    }
+}
+
+/// Number, sum and average length of finished breaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BreakStats {
+    pub count: usize,
+    pub total: Duration,
+    pub average: Duration,
 }
 
 pub(crate) struct BreakeState {
@@ -514,18 +1699,59 @@ pub(crate) struct BreakeState {
     pub sum: Duration,
 }
 
+/// Implausible entry in the time account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Anomaly {
+    /// Entry without any work.
+    Empty,
+    /// Entry with a negative duration.
+    Negative,
+    /// Entry of more than a day.
+    TooLong,
+    /// Entry stopped in the future.
+    Future,
+    /// Entry on the same day as a previous one with the same tags,
+    /// `canocicalize` merges them.
+    SameDay,
+    /// Entry started longer than the configured clock skew before the
+    /// previous one stopped, e.g. because the clock went backwards.
+    ClockSkew,
+}
+
+impl Anomaly {
+    /// Whether the entry can't be the result of real work and can be dropped.
+    pub fn is_impossible(&self) -> bool {
+        matches!(self, Anomaly::Empty | Anomaly::Negative | Anomaly::Future)
+    }
+}
+
+impl std::fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Anomaly::Empty => write!(f, "has a duration of zero"),
+            Anomaly::Negative => write!(f, "has a negative duration"),
+            Anomaly::TooLong => write!(f, "is longer than 24h"),
+            Anomaly::Future => write!(f, "is in the future"),
+            Anomaly::SameDay => write!(f, "is on the same day as the previous entry"),
+            Anomaly::ClockSkew => write!(
+                f,
+                "started long before the previous entry stopped, maybe the clock was wrong"
+            ),
+        }
+    }
+}
+
 impl std::fmt::Display for TimeBalance {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for (s, d) in self.time_account.iter() {
             let local = s.with_timezone(&Local).format("%d/%m/%Y, %H:%M");
-            let dur = Duration::from(d);
-            writeln!(
-                f,
-                "{}: {}:{}h",
-                local,
-                dur.num_hours(),
-                dur.num_minutes() % 60
-            )?;
+            let cfg = self.config.as_ref().unwrap_or_default();
+            write!(f, "{}: {}", local, cfg.format_duration(d.into()))?;
+            match d.breaks.len() {
+                0 => writeln!(f)?,
+                1 => writeln!(f, " (1 break)")?,
+                n => writeln!(f, " ({} breaks)", n)?,
+            }
         }
         Ok(())
     }
@@ -535,26 +1761,70 @@ impl TryFrom<&WorkStorage> for TimeBalance {
     type Error = Error;
     fn try_from(other: &WorkStorage) -> Result<Self, Self::Error> {
         let start = other.try_start().map(|s| s.start).ok();
-        let breaking = other.try_break().map(|b| b.start).ok();
-        let breaks = Vec::new();
-        let time_account: BTreeMap<DateTime<Utc>, DurationDef> = other
+        let breaking = other
+            .try_break()
+            .ok()
+            .filter(|b| b.duration.is_zero())
+            .map(|b| b.start);
+        let mut breaks = Vec::new();
+        // Legacy work is stored by its start, collect it with its stop which
+        // grows by every break attached to it.
+        let mut work = other
             .work_sets
             .iter()
-            .filter_map(|e| {
-                if e.ty == crate::storage::WorkType::Work {
-                    Some((e.start, Duration::from_std(e.duration).unwrap().into()))
-                } else {
-                    None
-                }
+            .filter(|e| e.ty == crate::storage::WorkType::Work)
+            .map(|e| {
+                let dur = Duration::from_std(e.duration)?;
+                Ok((
+                    e.start,
+                    e.start + dur,
+                    WorkEntry::from(DurationDef::from(dur)),
+                ))
             })
+            .collect::<Result<Vec<_>>>()?;
+        work.sort_by_key(|(s, _, _)| *s);
+        let mut finished = other
+            .work_sets
+            .iter()
+            .filter(|e| e.ty == crate::storage::WorkType::Break && !e.duration.is_zero())
+            .map(|b| Ok((b.start, Duration::from_std(b.duration)?)))
+            .collect::<Result<Vec<_>>>()?;
+        finished.sort_by_key(|(s, _)| *s);
+
+        // Finished breaks belong to the running work if they started after
+        // it, otherwise to the work whose span they start in.
+        for (b_start, dur) in finished {
+            if start.is_some_and(|s| s <= b_start) {
+                breaks.push((b_start, dur.into()));
+                continue;
+            }
+            match work
+                .iter_mut()
+                .rev()
+                .find(|(s, _, _)| *s <= b_start)
+                .filter(|(_, stop, _)| b_start < *stop)
+            {
+                Some((_, stop, entry)) => {
+                    *stop += dur;
+                    entry.breaks.push((b_start, dur.into()));
+                }
+                None => breaks.push((b_start, dur.into())),
+            }
+        }
+        let time_account: BTreeMap<DateTime<Utc>, WorkEntry> = work
+            .into_iter()
+            .map(|(_, stop, entry)| (stop, entry))
             .collect();
 
         Ok(Self {
+            name: Some(other.name.clone()),
             start,
             breaking,
             breaks,
             config: None,
             time_account,
+            adjustments: Vec::new(),
+            day_markers: BTreeMap::new(),
         })
     }
 }
@@ -565,6 +1835,18 @@ mod tests {
 
     use super::*;
 
+    /// The local time `h:min` on `y-m-d` in UTC.
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
     #[test]
     fn from_file_works() {
         let naive = NaiveDate::from_ymd_opt(2021, 1, 27)
@@ -577,10 +1859,11 @@ mod tests {
             + &utc_dt.to_rfc3339_opts(SecondsFormat::Secs, true)
             + r#"":{"secs":10,"nanos":0}}}"#;
         println!("{}", input);
-        let balance = TimeBalance::from_reader(&mut input.as_bytes()).expect("Failed to serialize");
+        let balance = TimeBalance::from_reader(&mut input.as_bytes(), StorageFormat::Json)
+            .expect("Failed to serialize");
 
         let mut expected = TimeBalance::new();
-        expected.insert(utc_dt, dur);
+        expected.insert(utc_dt, dur.into());
         assert_eq!(balance, expected);
     }
 
@@ -592,11 +1875,13 @@ mod tests {
             .and_hms_opt(14, 19, 21)
             .unwrap();
         let utc_dt = DateTime::from_naive_utc_and_offset(naive, chrono::Utc);
-        let dur = Duration::seconds(10).into();
-        balance.insert(utc_dt, dur);
+        let dur: DurationDef = Duration::seconds(10).into();
+        balance.insert(utc_dt, dur.into());
 
         let mut bytes: Vec<u8> = Vec::new();
-        balance.write(&mut bytes).expect("serialize works");
+        balance
+            .write(&mut bytes, StorageFormat::Json)
+            .expect("serialize works");
 
         let json = std::str::from_utf8(&bytes).expect("Bytes represent a string.");
         println!("{}", json);
@@ -610,13 +1895,166 @@ mod tests {
     fn cancel_break() {
         let mut balance = TimeBalance::new();
         assert!(balance.cancel().is_err());
-        balance.start(Utc::now()).expect("Starting works");
-        balance.start_break(Utc::now()).expect("break works");
+        balance
+            .start(Utc::now(), Utc::now())
+            .expect("Starting works");
+        balance
+            .start_break(Utc::now(), Utc::now())
+            .expect("break works");
         balance.cancel().expect("Cancel of break works");
         balance.cancel().expect("Cancel of start works");
         assert!(balance.cancel().is_err());
     }
 
+    #[test]
+    fn cancel_all() {
+        let mut balance = TimeBalance::new();
+        assert!(balance.cancel_all().is_err());
+
+        let start = Utc.with_ymd_and_hms(2022, 1, 12, 8, 0, 0).unwrap();
+        balance.start(start, start).expect("starting works");
+        balance.cancel_all().expect("cancel of start works");
+        assert_eq!(balance.start, None);
+
+        balance.start(start, start).expect("starting works");
+        let br = start + Duration::hours(1);
+        balance.start_break(br, br).expect("break works");
+        balance
+            .finish_break(br + Duration::minutes(10))
+            .expect("finishing break works");
+        balance
+            .start_break(br + Duration::hours(1), Utc::now())
+            .expect("break works");
+        balance.cancel_all().expect("cancel on break works");
+        assert_eq!(balance.start, None);
+        assert_eq!(balance.breaking, None);
+        assert!(balance.breaks.is_empty());
+        assert!(balance.cancel_all().is_err());
+    }
+
+    #[test]
+    fn finish_break_validates() {
+        let mut balance = TimeBalance::new();
+        let start = Utc.with_ymd_and_hms(2022, 1, 12, 8, 0, 0).unwrap();
+        balance.start(start, start).expect("starting works");
+        assert!(balance.finish_break(start).is_err());
+
+        let br = start + Duration::hours(1);
+        balance.start_break(br, br).expect("break works");
+        assert!(balance.finish_break(br - Duration::minutes(1)).is_err());
+        balance
+            .finish_break(br + Duration::minutes(10))
+            .expect("finishing break works");
+        let err = balance
+            .finish_break(br + Duration::minutes(20))
+            .expect_err("no break running");
+        assert!(err.to_string().contains("last break"));
+    }
+
+    #[test]
+    fn cancel_break_at() {
+        let mut balance = TimeBalance::new();
+        let start = Utc.with_ymd_and_hms(2022, 1, 12, 8, 0, 0).unwrap();
+        balance.start(start, start).expect("starting works");
+        for offset in [1, 3] {
+            let br = start + Duration::hours(offset);
+            balance.start_break(br, br).expect("break works");
+            balance
+                .finish_break(br + Duration::minutes(10))
+                .expect("finishing break works");
+        }
+        let first = (start + Duration::hours(1)).with_timezone(&Local).time();
+        let err = balance
+            .cancel_break_at(first + Duration::minutes(1))
+            .expect_err("no break at that time");
+        assert!(err.to_string().contains("breaks started at"));
+        assert_eq!(
+            balance.cancel_break_at(first).expect("canceling works"),
+            Duration::minutes(10)
+        );
+        assert_eq!(balance.breaks.len(), 1);
+        assert_eq!(balance.breaks[0].0, start + Duration::hours(3));
+    }
+
+    #[test]
+    fn dates_range() {
+        let mut balance = TimeBalance::new();
+        let day = Utc.with_ymd_and_hms(2022, 1, 12, 12, 0, 0).unwrap();
+        for offset in 0..3 {
+            let entry = WorkEntry::from(DurationDef::from(Duration::hours(1)));
+            balance.insert(day + Duration::days(offset), entry);
+        }
+        let since = day.date_naive();
+        let range: Vec<_> = balance
+            .dates_range(since, since + Duration::days(1), Utc)
+            .expect("range works")
+            .collect();
+        assert_eq!(range.len(), 2);
+        assert_eq!(*range[1].0, day + Duration::days(1));
+    }
+
+    #[test]
+    fn extremes() {
+        let mut balance = TimeBalance::new();
+        assert_eq!(balance.extremes(), None);
+        let day = Utc.with_ymd_and_hms(2022, 1, 12, 12, 0, 0).unwrap();
+        for (offset, minutes) in [(0, 300), (0, 240), (1, 360), (2, 2), (3, 420)] {
+            let entry = WorkEntry::from(DurationDef::from(Duration::minutes(minutes)));
+            balance.insert(
+                day + Duration::days(offset) + Duration::seconds(minutes),
+                entry,
+            );
+        }
+        let date = |offset| {
+            (day + Duration::days(offset))
+                .with_timezone(&Local)
+                .date_naive()
+        };
+        assert_eq!(
+            balance.extremes(),
+            Some((
+                (date(1), Duration::minutes(360)),
+                (date(0), Duration::minutes(540))
+            ))
+        );
+    }
+
+    #[test]
+    fn month_boundary() {
+        let month_sum = |balance: &TimeBalance, month| {
+            balance
+                .month_range(2022, month)
+                .expect("range works")
+                .fold(Duration::zero(), |acc, (_, e)| acc + Duration::from(e))
+        };
+        let start = Local
+            .with_ymd_and_hms(2022, 1, 31, 23, 30, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let stop = start + Duration::minutes(90);
+
+        let mut balance = TimeBalance::new();
+        let entry = WorkEntry::from(DurationDef::from(Duration::minutes(90)));
+        balance.insert(stop, entry);
+        assert_eq!(month_sum(&balance, Month::January), Duration::zero());
+        assert_eq!(month_sum(&balance, Month::February), Duration::minutes(90));
+
+        let mut balance = TimeBalance::new();
+        balance.config = Some(Config {
+            split_over_midnight: Some(true),
+            ..Default::default()
+        });
+        balance.start(start, start).expect("starting works");
+        balance.stop(stop, None).expect("stopping works");
+        let midnight = start + Duration::minutes(30);
+        assert_eq!(
+            balance.time_account.keys().copied().collect::<Vec<_>>(),
+            vec![midnight, stop]
+        );
+        assert_eq!(month_sum(&balance, Month::January), Duration::minutes(30));
+        assert_eq!(month_sum(&balance, Month::February), Duration::minutes(60));
+    }
+
     #[test]
     fn daily_range() {
         let mut balance = TimeBalance::new();
@@ -627,17 +2065,17 @@ mod tests {
         {
             let start = Utc::now();
             balance
-                .start(start - Duration::seconds(5))
+                .start(start - Duration::seconds(5), Utc::now())
                 .expect("starting works");
-            balance.stop(start).expect("stopping works");
-            let range: Vec<(&DateTime<Utc>, &DurationDef)> = balance
+            balance.stop(start, None).expect("stopping works");
+            let range: Vec<(&DateTime<Utc>, &WorkEntry)> = balance
                 .daily_range(Utc::now().date_naive(), Utc)
                 .expect("range works")
                 .collect();
             assert_eq!(range.len(), 1);
             assert_eq!(
                 *range.first().expect("has length 1"),
-                (&start, &Duration::seconds(5).into())
+                (&start, &DurationDef::from(Duration::seconds(5)).into())
             );
         }
 
@@ -649,23 +2087,272 @@ mod tests {
                 .and_local_timezone(Utc)
                 .earliest()
                 .unwrap();
-            balance.start(start).expect("Starting works");
+            // Bypass the check for starts in the future, this might run before 20:55.
+            balance.start = Some(start);
             let stop = start
                 .checked_add_signed(Duration::minutes(90))
                 .expect("adding works");
-            balance.stop(stop).expect("stopping works");
-            let range: Vec<(&DateTime<Utc>, &DurationDef)> = balance
+            balance.stop(stop, None).expect("stopping works");
+            let range: Vec<(&DateTime<Utc>, &WorkEntry)> = balance
                 .daily_range(Utc::now().date_naive(), Utc)
                 .expect("range works")
                 .collect();
             assert_eq!(dbg!(&range).len(), 2);
             assert_eq!(
                 *range.get(1).expect("has length 2"),
-                (&stop, &Duration::minutes(90).into())
+                (&stop, &DurationDef::from(Duration::minutes(90)).into())
             );
         }
     }
 
+    #[test]
+    fn start_in_future() {
+        let mut balance = TimeBalance::new();
+        let now = Utc::now();
+        let err = balance
+            .start(now + Duration::seconds(MAX_FUTURE_SECS + 60), now)
+            .expect_err("starting in the future fails");
+        assert!(err.to_string().contains("in the future"));
+        balance
+            .start(now + Duration::seconds(MAX_FUTURE_SECS - 10), now)
+            .expect("starting within tolerance works");
+        assert!(balance
+            .start_break(now + Duration::seconds(MAX_FUTURE_SECS + 60), now)
+            .is_err());
+        balance.start_break(now, now).expect("break works");
+        assert!(balance.start(now, now).is_err());
+    }
+
+    #[test]
+    fn stop_before_start() {
+        let mut balance = TimeBalance::new();
+        let start = Utc.with_ymd_and_hms(2022, 1, 12, 9, 0, 0).unwrap();
+        balance.start(start, start).expect("starting works");
+        let err = balance
+            .stop(start - Duration::hours(1), None)
+            .expect_err("stopping before start fails");
+        assert!(err.to_string().contains("is before start"));
+        assert_eq!(balance.start, Some(start));
+        assert!(balance.time_account.is_empty());
+    }
+
+    #[test]
+    fn stop_splits_over_midnight() {
+        let mut balance = TimeBalance::new();
+        balance.config = Some(Config {
+            split_over_midnight: Some(true),
+            ..Default::default()
+        });
+        let start = Local
+            .with_ymd_and_hms(2022, 1, 12, 22, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let midnight = Local
+            .with_ymd_and_hms(2022, 1, 13, 0, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        balance.start(start, start).expect("starting works");
+        balance
+            .start_break(start + Duration::minutes(30), Utc::now())
+            .expect("break works");
+        balance
+            .finish_break(start + Duration::minutes(45))
+            .expect("finishing break works");
+        let dur = balance
+            .stop(start + Duration::hours(4), None)
+            .expect("stopping works");
+        assert_eq!(dur, Duration::minutes(225));
+        let stop = start + Duration::hours(4);
+        assert_eq!(
+            balance.time_account.keys().copied().collect::<Vec<_>>(),
+            vec![midnight, stop]
+        );
+        let first = &balance.time_account[&midnight];
+        assert_eq!(Duration::from(first), Duration::minutes(105));
+        assert_eq!(first.breaks.len(), 1);
+        assert_eq!(first.start(midnight), start);
+        let second = &balance.time_account[&stop];
+        assert_eq!(Duration::from(second), Duration::hours(2));
+        assert_eq!(second.start(stop), midnight);
+
+        let day: Vec<_> = balance
+            .daily_range(start.with_timezone(&Local).date_naive(), Local)
+            .expect("range works")
+            .collect();
+        assert_eq!(day.len(), 1);
+        balance.canocicalize().expect("canocicalize works");
+        assert_eq!(balance.time_account.len(), 2);
+
+        // A break over midnight is split at midnight, too.
+        let mut balance = TimeBalance::new();
+        balance.config = Some(Config {
+            split_over_midnight: Some(true),
+            ..Default::default()
+        });
+        balance.start(start, start).expect("starting works");
+        balance
+            .start_break(midnight - Duration::minutes(15), Utc::now())
+            .expect("break works");
+        balance
+            .finish_break(midnight + Duration::minutes(15))
+            .expect("finishing break works");
+        let dur = balance.stop(stop, None).expect("stopping works");
+        assert_eq!(dur, Duration::minutes(210));
+        let first = &balance.time_account[&midnight];
+        assert_eq!(Duration::from(first), Duration::minutes(105));
+        assert_eq!(first.break_sum(), Duration::minutes(15));
+        assert_eq!(first.start(midnight), start);
+        let second = &balance.time_account[&stop];
+        assert_eq!(Duration::from(second), Duration::minutes(105));
+        assert_eq!(
+            second.breaks,
+            vec![(midnight, Duration::minutes(15).into())]
+        );
+        assert_eq!(second.start(stop), midnight);
+    }
+
+    #[test]
+    fn continue_last() {
+        let mut balance = TimeBalance::new();
+        assert!(balance.continue_last().is_err());
+
+        let start = Utc.with_ymd_and_hms(2022, 1, 12, 8, 0, 0).unwrap();
+        balance.start(start, start).expect("starting works");
+        balance
+            .start_break(start + Duration::hours(1), Utc::now())
+            .expect("break works");
+        balance
+            .finish_break(start + Duration::minutes(90))
+            .expect("finishing break works");
+        balance
+            .stop(start + Duration::hours(3), None)
+            .expect("stopping works");
+
+        let worked = balance.continue_last().expect("continuing works");
+        assert_eq!(worked, Duration::minutes(150));
+        assert_eq!(balance.start, Some(start));
+        assert_eq!(balance.breaks.len(), 1);
+        assert!(balance.time_account.is_empty());
+        assert!(balance.continue_last().is_err());
+
+        let dur = balance
+            .stop(start + Duration::hours(4), None)
+            .expect("stopping works");
+        assert_eq!(dur, Duration::minutes(210));
+    }
+
+    #[test]
+    fn stop_with_note() {
+        let mut balance = TimeBalance::new();
+        let start = Utc.with_ymd_and_hms(2022, 1, 12, 8, 0, 0).unwrap();
+        balance.start(start, start).expect("starting works");
+        let stop = start + Duration::hours(4);
+        balance
+            .stop(stop, Some("shipped release".to_string()))
+            .expect("stopping works");
+        balance.start(stop, stop).expect("starting works");
+        balance
+            .stop(stop + Duration::hours(1), Some(" ".to_string()))
+            .expect("stopping works");
+        let entry = &balance.time_account[&(stop + Duration::hours(1))];
+        assert_eq!(entry.note, None);
+        assert!(!serde_json::to_string(entry).unwrap().contains("note"));
+
+        balance.canocicalize().expect("merging works");
+        let entry = balance.time_account.values().next().expect("entry exists");
+        assert_eq!(entry.note.as_deref(), Some("shipped release"));
+    }
+
+    #[test]
+    fn stop_keeps_breaks() {
+        let mut balance = TimeBalance::new();
+        let start = Utc.with_ymd_and_hms(2022, 1, 12, 8, 0, 0).unwrap();
+        balance.start(start, start).expect("starting works");
+        for offset in [2, 4] {
+            let br = start + Duration::hours(offset);
+            balance.start_break(br, br).expect("break works");
+            balance
+                .finish_break(br + Duration::minutes(15))
+                .expect("finishing break works");
+        }
+        let stop = start + Duration::hours(8);
+        let dur = balance.stop(stop, None).expect("stopping works");
+        assert_eq!(dur, Duration::minutes(450));
+        assert!(balance.breaks.is_empty());
+
+        let entry = balance.time_account.get(&stop).expect("entry exists");
+        assert_eq!(entry.breaks.len(), 2);
+        assert_eq!(entry.breaks[1].0, start + Duration::hours(4));
+        assert_eq!(entry.break_sum(), Duration::minutes(30));
+        let breaks = balance
+            .breaks_on(start.date_naive(), Utc)
+            .expect("range works");
+        assert_eq!(breaks.len(), 2);
+
+        let mut bytes: Vec<u8> = Vec::new();
+        balance
+            .write(&mut bytes, StorageFormat::Json)
+            .expect("serialize works");
+        let read = TimeBalance::from_reader(&mut bytes.as_slice(), StorageFormat::Json)
+            .expect("deserialize works");
+        assert_eq!(read, balance);
+        assert!(!bytes.contains(&b'\n'));
+
+        balance.config = Some(Config {
+            pretty_json: Some(true),
+            ..Default::default()
+        });
+        let mut bytes: Vec<u8> = Vec::new();
+        balance
+            .write(&mut bytes, StorageFormat::Json)
+            .expect("serialize works");
+        assert!(bytes.contains(&b'\n'));
+        let read = TimeBalance::from_reader(&mut bytes.as_slice(), StorageFormat::Json)
+            .expect("deserialize works");
+        assert_eq!(read, balance);
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn toml_roundtrip() {
+        assert_eq!(
+            StorageFormat::from_path("stempel.toml"),
+            StorageFormat::Toml
+        );
+        assert_eq!(
+            StorageFormat::from_path("stempel.json"),
+            StorageFormat::Json
+        );
+        assert_eq!(StorageFormat::from_path("stempel"), StorageFormat::Json);
+
+        let mut balance = TimeBalance::new();
+        balance.config = Some(Config::default());
+        let start = Utc.with_ymd_and_hms(2022, 1, 12, 8, 0, 0).unwrap();
+        balance.start(start, start).expect("starting works");
+        balance
+            .start_break(start + Duration::hours(1), Utc::now())
+            .expect("break works");
+        balance
+            .finish_break(start + Duration::hours(2))
+            .expect("finishing break works");
+        balance
+            .stop(start + Duration::hours(3), None)
+            .expect("stopping works");
+        balance
+            .start(start + Duration::hours(5), Utc::now())
+            .expect("starting works");
+
+        let mut bytes: Vec<u8> = Vec::new();
+        balance
+            .write(&mut bytes, StorageFormat::Toml)
+            .expect("serialize works");
+        let toml = std::str::from_utf8(&bytes).expect("Bytes represent a string.");
+        assert!(toml.contains(r#"[account."2022-01-12T11:00:00Z"]"#));
+        let read = TimeBalance::from_reader(&mut bytes.as_slice(), StorageFormat::Toml)
+            .expect("deserialize works");
+        assert_eq!(read, balance);
+    }
+
     #[test]
     fn stringify() {
         let dur = Duration::nanoseconds(10)
@@ -679,6 +2366,30 @@ mod tests {
 
         let dur_back = Duration::from(&durdef);
         assert_eq!(dur_back, durdef.into());
+
+        let durdef = DurationDef::from(Duration::seconds(-(4 * 3600 + 40 * 60 + 5)));
+        assert_eq!(durdef.to_string(), "-04:40h");
+        assert_eq!(format!("{:#}", durdef), "-04:40:05h");
+    }
+
+    #[test]
+    fn decimal_hours_round_half_up() {
+        let decimal = |secs: i64| DurationDef::from(Duration::seconds(secs)).decimal();
+        assert_eq!(decimal(7 * 3600 + 30 * 60), "7.50");
+        assert_eq!(decimal(0), "0.00");
+        // 18s are exactly half of a hundredth of an hour.
+        assert_eq!(decimal(17), "0.00");
+        assert_eq!(decimal(18), "0.01");
+        assert_eq!(decimal(3600 + 20 * 60), "1.33");
+        assert_eq!(decimal(-(3600 + 40 * 60)), "-1.67");
+        assert_eq!(decimal(-18), "-0.01");
+
+        let cfg = Config {
+            decimal_hours: Some(true),
+            show_seconds: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(cfg.format_duration(Duration::minutes(450)), "7.50");
     }
 
     #[test]
@@ -691,7 +2402,7 @@ mod tests {
         };
         let br = WorkSet {
             ty: crate::storage::WorkType::Break,
-            duration: std::time::Duration::from_secs(1),
+            duration: std::time::Duration::from_secs(0),
             start: time,
         };
         let work = WorkSet {
@@ -706,20 +2417,68 @@ mod tests {
 
         let balance: TimeBalance = TimeBalance::try_from(&storage).expect("Conversion works");
         println!("{}", balance);
+        assert_eq!(balance.name.as_deref(), Some("test"));
         assert_eq!(balance.start, Some(time));
         assert_eq!(balance.breaking, Some(time));
     }
 
-    fn add_times(balance: &mut TimeBalance, dt: DateTime<Utc>, dur: i64) {
-        balance
-            .start(dt - Duration::minutes(dur))
-            .expect("starting works");
-        balance.stop(dt).expect("stopping works");
-    }
-
     #[test]
-    fn canocicalize_works() {
-        let mut balance = TimeBalance::new();
+    fn migrate_keeps_breaks() {
+        let day = local(2022, 1, 12, 8, 0);
+        let set = |ty, start, mins: u64| WorkSet {
+            ty,
+            duration: std::time::Duration::from_secs(mins * 60),
+            start,
+        };
+        let running = day + Duration::days(1);
+        let storage = WorkStorage {
+            name: "test".to_string(),
+            work_sets: vec![
+                set(crate::storage::WorkType::Work, day, 8 * 60),
+                set(
+                    crate::storage::WorkType::Break,
+                    day + Duration::hours(2),
+                    15,
+                ),
+                set(crate::storage::WorkType::Start, running, 0),
+                set(
+                    crate::storage::WorkType::Break,
+                    running + Duration::hours(1),
+                    10,
+                ),
+            ],
+        };
+
+        let balance = TimeBalance::try_from(&storage).expect("Conversion works");
+        let stop = day + Duration::minutes(8 * 60 + 15);
+        let entry = balance.time_account.get(&stop).expect("entry exists");
+        assert_eq!(entry.start(stop), day);
+        assert_eq!(
+            entry_day(&stop),
+            NaiveDate::from_ymd_opt(2022, 1, 12).unwrap()
+        );
+        assert_eq!(Duration::from(entry), Duration::hours(8));
+        assert_eq!(
+            entry.breaks,
+            vec![(day + Duration::hours(2), Duration::minutes(15).into())]
+        );
+        assert_eq!(
+            balance.breaks,
+            vec![(running + Duration::hours(1), Duration::minutes(10).into())]
+        );
+        assert_eq!(balance.breaking, None);
+    }
+
+    fn add_times(balance: &mut TimeBalance, dt: DateTime<Utc>, dur: i64) {
+        balance
+            .start(dt - Duration::minutes(dur), Utc::now())
+            .expect("starting works");
+        balance.stop(dt, None).expect("stopping works");
+    }
+
+    #[test]
+    fn canocicalize_works() {
+        let mut balance = TimeBalance::new();
         let now = Utc.with_ymd_and_hms(2022, 1, 12, 1, 20, 30).unwrap();
         add_times(&mut balance, now, 10);
         add_times(&mut balance, now + Duration::seconds(10), 12);
@@ -738,6 +2497,443 @@ mod tests {
         assert_eq!(sum, Duration::minutes(54));
     }
 
+    #[test]
+    fn earnings() {
+        let mut cfg = Config::default();
+        assert_eq!(cfg.earnings(Duration::hours(2)), None);
+        cfg.hourly_rate = Some(50.0);
+        assert_eq!(
+            cfg.earnings(Duration::minutes(90)),
+            Some("75.00".to_string())
+        );
+        cfg.currency = Some("€".to_string());
+        assert_eq!(
+            cfg.earnings(Duration::minutes(90)),
+            Some("75.00 €".to_string())
+        );
+    }
+
+    #[test]
+    fn anomalies() {
+        let mut balance = TimeBalance::new();
+        let now = Utc.with_ymd_and_hms(2022, 1, 12, 12, 0, 0).unwrap();
+        let day = Duration::days(1);
+        let entry = |d: Duration| WorkEntry::from(DurationDef::from(d));
+        balance.insert(now - day * 4, entry(Duration::zero()));
+        balance.insert(now - day * 3, entry(Duration::minutes(-10)));
+        balance.insert(now - day * 2, entry(Duration::hours(30)));
+        balance.insert(
+            now - day * 2 + Duration::hours(1),
+            entry(Duration::hours(1)),
+        );
+        balance.insert(now + day, entry(Duration::hours(1)));
+        assert_eq!(
+            balance
+                .anomalies(now)
+                .into_iter()
+                .map(|(_, a)| a)
+                .collect::<Vec<_>>(),
+            vec![
+                Anomaly::Empty,
+                Anomaly::Negative,
+                Anomaly::TooLong,
+                Anomaly::SameDay,
+                Anomaly::Future
+            ]
+        );
+
+        let dropped = balance.fix_anomalies(now).expect("fixing works");
+        assert_eq!(dropped, 3);
+        assert_eq!(balance.time_account.len(), 1);
+        assert_eq!(
+            balance.anomalies(now),
+            vec![(now - day * 2 + Duration::hours(1), Anomaly::TooLong)]
+        );
+    }
+
+    #[test]
+    fn clock_skew() {
+        let mut balance = TimeBalance::new();
+        let now = Utc.with_ymd_and_hms(2022, 1, 12, 12, 0, 0).unwrap();
+        assert_eq!(balance.skewed_start(now), None);
+        let entry = |h| WorkEntry::from(DurationDef::from(Duration::hours(h)));
+        balance.insert(now, entry(2));
+        assert_eq!(balance.skewed_start(now - Duration::minutes(30)), None);
+        assert_eq!(balance.skewed_start(now - Duration::hours(2)), Some(now));
+
+        balance.config = Some(Config {
+            clock_skew_hours: Some(3),
+            ..Default::default()
+        });
+        assert_eq!(balance.skewed_start(now - Duration::hours(2)), None);
+        assert_eq!(balance.skewed_start(now - Duration::hours(4)), Some(now));
+
+        // stopped a day later but started four hours before the previous stop
+        balance.insert(now + Duration::days(1), entry(28));
+        balance.insert(now + Duration::hours(2), entry(1));
+        assert_eq!(
+            balance.anomalies(now + Duration::days(2)),
+            vec![
+                (now + Duration::hours(2), Anomaly::SameDay),
+                (now + Duration::days(1), Anomaly::TooLong)
+            ]
+        );
+        balance.insert(now + Duration::hours(3), entry(6));
+        assert_eq!(
+            balance.anomalies(now + Duration::days(2)),
+            vec![
+                (now + Duration::hours(2), Anomaly::SameDay),
+                (now + Duration::hours(3), Anomaly::ClockSkew),
+                (now + Duration::hours(3), Anomaly::SameDay),
+                (now + Duration::days(1), Anomaly::TooLong)
+            ]
+        );
+    }
+
+    #[test]
+    fn canocicalize_merges_breaks() {
+        let mut balance = TimeBalance::new();
+        let now = Utc.with_ymd_and_hms(2022, 1, 12, 10, 0, 0).unwrap();
+        for offset in [0, 3] {
+            let start = now + Duration::hours(offset);
+            balance.start(start, start).expect("starting works");
+            balance.start_break(start, start).expect("break works");
+            balance
+                .finish_break(start + Duration::minutes(10))
+                .expect("finishing break works");
+            balance
+                .stop(start + Duration::hours(2), None)
+                .expect("stopping works");
+        }
+        balance.canocicalize().expect("Works");
+        assert_eq!(balance.time_account.len(), 1);
+        let entry = balance.time_account.values().next().expect("one entry");
+        assert_eq!(Duration::from(entry), Duration::minutes(220));
+        assert_eq!(entry.breaks.len(), 2);
+    }
+
+    #[test]
+    fn canocicalize_merges_four_entries() {
+        let mut balance = TimeBalance::new();
+        let noon = Utc.with_ymd_and_hms(2022, 1, 12, 12, 0, 0).unwrap();
+        for (i, minutes) in [30, 45, 60, 75].into_iter().enumerate() {
+            let stop = noon + Duration::seconds(i as i64);
+            let mut entry = WorkEntry::from(DurationDef::from(Duration::minutes(minutes)));
+            entry.note = Some(i.to_string());
+            balance.time_account.insert(stop, entry);
+        }
+        balance.time_account.insert(
+            noon + Duration::days(1),
+            DurationDef::from(Duration::minutes(10)).into(),
+        );
+        let before = balance.total_worked();
+        let day = noon.with_timezone(&Local).date_naive();
+        assert_eq!(balance.planned_merges(), vec![(day, 4)]);
+
+        balance.canocicalize().expect("Works");
+        assert!(balance.planned_merges().is_empty());
+        assert_eq!(balance.time_account.len(), 2);
+        assert_eq!(balance.total_worked(), before);
+        let merged = &balance.time_account[&(noon + Duration::seconds(3))];
+        assert_eq!(Duration::from(merged), Duration::minutes(210));
+        assert_eq!(merged.note.as_deref(), Some("0; 1; 2; 3"));
+    }
+
+    #[test]
+    fn canocicalize_keeps_sessions_if_disabled() {
+        let mut balance = TimeBalance::new();
+        balance.config = Some(Config {
+            merge_same_day: Some(false),
+            ..Default::default()
+        });
+        let noon = Utc.with_ymd_and_hms(2022, 1, 12, 12, 0, 0).unwrap();
+        for stop in [noon, noon + Duration::hours(4)] {
+            balance
+                .time_account
+                .insert(stop, DurationDef::from(Duration::hours(3)).into());
+        }
+        assert!(balance.planned_merges().is_empty());
+        assert!(balance.anomalies(noon + Duration::hours(4)).is_empty());
+
+        balance.canocicalize().expect("Works");
+        assert_eq!(balance.time_account.len(), 2);
+        assert_eq!(balance.total_worked(), Duration::hours(6));
+    }
+
+    #[test]
+    fn canocicalize_merges_random_entries() {
+        // Linear congruential generator, good enough to shuffle test data.
+        let mut seed: u64 = 0x5eed;
+        let mut random = |max: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % max
+        };
+        let morning = NaiveDate::from_ymd_opt(2022, 1, 12)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc);
+        for _ in 0..50 {
+            let mut balance = TimeBalance::new();
+            let count = 3 + random(10);
+            let mut total = Duration::zero();
+            let mut breaks = 0;
+            for _ in 0..count {
+                let stop = morning + Duration::seconds(random(8 * 3600) as i64);
+                let dur = Duration::seconds(1 + random(3600) as i64);
+                let mut entry = WorkEntry::from(DurationDef::from(dur));
+                if random(2) == 0 {
+                    entry.breaks.push((stop - dur, Duration::minutes(5).into()));
+                }
+                if let Some(prev) = balance.time_account.insert(stop, entry.clone()) {
+                    // Same key drawn twice, keep the first entry.
+                    balance.time_account.insert(stop, prev);
+                    continue;
+                }
+                total += dur;
+                breaks += entry.breaks.len();
+            }
+
+            balance.canocicalize().expect("Works");
+            assert_eq!(balance.time_account.len(), 1);
+            let merged = balance.time_account.values().next().unwrap();
+            assert_eq!(Duration::from(merged), total);
+            assert_eq!(merged.breaks.len(), breaks);
+        }
+    }
+
+    #[test]
+    fn total_worked() {
+        let mut balance = TimeBalance::new();
+        assert_eq!(balance.total_worked(), Duration::zero());
+        assert_eq!(balance.worked_days(), 0);
+
+        let now = Utc.with_ymd_and_hms(2022, 1, 12, 12, 20, 30).unwrap();
+        add_times(&mut balance, now, 70);
+        add_times(&mut balance, now + Duration::seconds(10), 12);
+        add_times(&mut balance, now + Duration::days(1), 30);
+        assert_eq!(balance.total_worked(), Duration::minutes(112));
+        assert_eq!(balance.worked_days(), 2);
+        balance.canocicalize().expect("canocicalize works");
+        assert_eq!(balance.total_worked(), Duration::minutes(112));
+        assert_eq!(balance.worked_days(), 2);
+    }
+
+    #[test]
+    fn overhours_for_month() {
+        let mut balance = TimeBalance::new();
+        let month = Month::January;
+        assert_eq!(balance.overhours_for_month(2022, month).unwrap(), None);
+        balance.config = Some(Config {
+            daily_hours: Some(8),
+            ..Default::default()
+        });
+        assert_eq!(
+            balance.overhours_for_month(2022, month).unwrap(),
+            Some(Duration::zero())
+        );
+
+        let day = Utc.with_ymd_and_hms(2022, 1, 12, 12, 0, 0).unwrap();
+        for (offset, hours) in [(0, 5), (0, 4), (1, 6), (40, 10)] {
+            let entry = WorkEntry::from(DurationDef::from(Duration::hours(hours)));
+            balance.insert(
+                day + Duration::days(offset) + Duration::minutes(hours),
+                entry,
+            );
+        }
+        assert_eq!(
+            balance.overhours_for_month(2022, month).unwrap(),
+            Some(Duration::hours(-1))
+        );
+        assert_eq!(
+            balance.overhours_for_month(2022, Month::February).unwrap(),
+            Some(Duration::hours(2))
+        );
+    }
+
+    #[test]
+    fn pay_periods() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let mut cfg = Config::default();
+        assert_eq!(cfg.pay_period(date(2024, 6, 10)), None);
+
+        cfg.period_start_day = Some(26);
+        assert_eq!(
+            cfg.pay_period(date(2024, 6, 10)),
+            Some((date(2024, 5, 26), date(2024, 6, 25)))
+        );
+        assert_eq!(
+            cfg.pay_period(date(2024, 6, 26)),
+            Some((date(2024, 6, 26), date(2024, 7, 25)))
+        );
+        assert_eq!(
+            cfg.pay_period(date(2024, 1, 3)),
+            Some((date(2023, 12, 26), date(2024, 1, 25)))
+        );
+        assert_eq!(
+            cfg.pay_period(date(2024, 12, 31)),
+            Some((date(2024, 12, 26), date(2025, 1, 25)))
+        );
+
+        cfg.period_start_day = Some(1);
+        assert_eq!(
+            cfg.pay_period(date(2024, 2, 29)),
+            Some((date(2024, 2, 1), date(2024, 2, 29)))
+        );
+        cfg.period_start_day = Some(31);
+        assert_eq!(
+            cfg.pay_period(date(2023, 3, 1)),
+            Some((date(2023, 2, 28), date(2023, 3, 27)))
+        );
+
+        let mut balance = TimeBalance::new();
+        let morning = |day: NaiveDate| local(day.year(), day.month(), day.day(), 10, 0);
+        for (day, hours) in [
+            (date(2024, 5, 27), 9),
+            (date(2024, 6, 25), 6),
+            (date(2024, 6, 26), 10),
+        ] {
+            let entry = WorkEntry::from(DurationDef::from(Duration::hours(hours)));
+            balance.insert(morning(day) + Duration::hours(hours), entry);
+        }
+        balance.config = Some(Config {
+            daily_hours: Some(8),
+            period_start_day: Some(26),
+            ..Default::default()
+        });
+        let (since, until) = balance
+            .config
+            .as_ref()
+            .unwrap()
+            .pay_period(date(2024, 6, 1))
+            .unwrap();
+        assert_eq!(
+            balance.overhours_for_range(since, until).unwrap(),
+            Some(Duration::hours(-1))
+        );
+        assert_eq!(
+            balance.overhours_for_month(2024, Month::June).unwrap(),
+            Some(Duration::zero())
+        );
+    }
+
+    #[test]
+    fn grace_period_per_day() {
+        let mut balance = TimeBalance::new();
+        let morning = |day| local(2022, 1, day, 10, 0);
+        for (day, minutes) in [(10, 485), (11, 240), (11, 230), (12, 540), (13, 360)] {
+            let entry = WorkEntry::from(DurationDef::from(Duration::minutes(minutes)));
+            balance.insert(morning(day) + Duration::minutes(minutes), entry);
+        }
+        balance.config = Some(Config {
+            daily_hours: Some(8),
+            ..Default::default()
+        });
+        assert_eq!(balance.calculate_overhours(), Some(Duration::minutes(-65)));
+        balance.config = Some(Config {
+            daily_hours: Some(8),
+            daily_grace_minutes: Some(10),
+            ..Default::default()
+        });
+        assert_eq!(balance.calculate_overhours(), Some(Duration::minutes(-60)));
+        assert_eq!(
+            balance.overhours_for_month(2022, Month::January).unwrap(),
+            Some(Duration::minutes(-60))
+        );
+    }
+
+    #[test]
+    fn overtime_per_day_of_unmerged_entries() {
+        let mut balance = TimeBalance::new();
+        balance.config = Some(Config {
+            daily_hours: Some(8),
+            merge_same_day: Some(false),
+            ..Default::default()
+        });
+        // Two halves of one day and a short day.
+        for stop in [12, 17] {
+            let entry = WorkEntry::from(DurationDef::from(Duration::hours(4)));
+            balance.insert(local(2022, 1, 10, stop, 0), entry);
+        }
+        let entry = WorkEntry::from(DurationDef::from(Duration::hours(6)));
+        balance.insert(local(2022, 1, 11, 14, 0), entry);
+        balance.canocicalize().unwrap();
+        assert_eq!(balance.time_account.len(), 3);
+        assert_eq!(balance.calculate_overhours(), Some(Duration::hours(-2)));
+        assert_eq!(
+            balance.overhours_for_month(2022, Month::January).unwrap(),
+            Some(Duration::hours(-2))
+        );
+    }
+
+    #[test]
+    fn warn_near_overtime_cap() {
+        let mut cfg = Config::default();
+        assert_eq!(cfg.cap_left(Duration::hours(40)), None);
+        cfg.overtime_cap_hours = Some(40);
+        assert_eq!(cfg.cap_left(Duration::hours(35)), None);
+        assert_eq!(
+            cfg.cap_left(Duration::hours(37)),
+            Some((Duration::hours(3), Duration::hours(40)))
+        );
+        assert_eq!(cfg.cap_left(Duration::hours(40)), None);
+        cfg.cap_warn_percent = Some(50);
+        assert_eq!(
+            cfg.cap_left(Duration::hours(20)),
+            Some((Duration::hours(20), Duration::hours(40)))
+        );
+        assert_eq!(cfg.cap_left(Duration::hours(-37)), None);
+    }
+
+    #[test]
+    fn break_stats_cover_history() {
+        let mut balance = TimeBalance::new();
+        assert_eq!(balance.break_stats(), None);
+        let morning = NaiveDate::from_ymd_opt(2022, 1, 12)
+            .unwrap()
+            .and_hms_opt(6, 0, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc);
+        balance.start(morning, morning).unwrap();
+        balance
+            .start_break(morning + Duration::hours(1), Utc::now())
+            .unwrap();
+        balance
+            .finish_break(morning + Duration::minutes(70))
+            .unwrap();
+        balance
+            .start_break(morning + Duration::hours(2), Utc::now())
+            .unwrap();
+        balance
+            .finish_break(morning + Duration::minutes(140))
+            .unwrap();
+        balance.stop(morning + Duration::hours(4), None).unwrap();
+        let evening = morning + Duration::hours(10);
+        balance.start(evening, evening).unwrap();
+        balance
+            .start_break(evening + Duration::hours(1), Utc::now())
+            .unwrap();
+        balance
+            .finish_break(evening + Duration::minutes(90))
+            .unwrap();
+        assert_eq!(
+            balance.break_stats(),
+            Some(BreakStats {
+                count: 3,
+                total: Duration::minutes(60),
+                average: Duration::minutes(20),
+            })
+        );
+    }
+
     #[test]
     fn overhours_work() {
         let mut balance = TimeBalance::new();
@@ -764,5 +2960,397 @@ mod tests {
         add_times(&mut balance, now + Duration::days(30), 58);
         let overhours = balance.calculate_overhours();
         assert_eq!(overhours, Some(Duration::minutes(24)));
+
+        balance.config = Some(Config {
+            daily_hours: Some(1),
+            initial_overtime_minutes: Some(-90),
+            ..Default::default()
+        });
+        let overhours = balance.calculate_overhours();
+        assert_eq!(overhours, Some(Duration::minutes(-66)));
+
+        balance.adjust_overtime(now, Duration::minutes(-30));
+        balance.adjust_overtime(now, Duration::minutes(10));
+        let overhours = balance.calculate_overhours();
+        assert_eq!(overhours, Some(Duration::minutes(-86)));
+        balance.adjustments.clear();
+
+        balance.config = Some(Config {
+            daily_hours: Some(1),
+            initial_overtime_minutes: Some(-90),
+            overtime_cap_hours: Some(1),
+            ..Default::default()
+        });
+        let overhours = balance.calculate_overhours();
+        assert_eq!(overhours, Some(Duration::minutes(-60)));
+
+        balance.config = Some(Config {
+            daily_hours: Some(1),
+            holidays: vec![(now - Duration::days(20)).date_naive()],
+            vacations: vec![(now + Duration::days(30)).date_naive()],
+            ..Default::default()
+        });
+        let overhours = balance.calculate_overhours();
+        assert_eq!(overhours, Some(Duration::minutes(144)));
+
+        balance.config = Some(Config {
+            daily_hours: Some(1),
+            tracking_start_date: Some((now + Duration::days(30)).date_naive()),
+            ..Default::default()
+        });
+        let overhours = balance.calculate_overhours();
+        assert_eq!(overhours, Some(Duration::minutes(-2)));
+    }
+
+    #[test]
+    fn day_markers() {
+        let day = |d| NaiveDate::from_ymd_opt(2022, 1, d).unwrap();
+        let mut balance = TimeBalance::new();
+        assert_eq!(balance.day_marker(day(10)), None);
+        assert_eq!(balance.mark_day(day(10), DayKind::Sick), None);
+        assert_eq!(
+            balance.mark_day(day(10), DayKind::Vacation),
+            Some(DayKind::Sick)
+        );
+        balance.mark_day(day(10), DayKind::Sick);
+        balance.config = Some(Config {
+            daily_hours: Some(8),
+            holidays: vec![day(10), day(12)],
+            vacations: vec![day(14)],
+            ..Default::default()
+        });
+        assert_eq!(balance.day_marker(day(10)), Some(DayKind::Sick));
+        assert_eq!(balance.day_marker(day(12)), Some(DayKind::Holiday));
+        assert!(balance.is_day_off(day(14)));
+        assert!(!balance.is_day_off(day(13)));
+        assert_eq!(
+            balance.marked_days(day(11), day(14)),
+            vec![(day(12), DayKind::Holiday), (day(14), DayKind::Vacation)]
+        );
+
+        let stop = day(10)
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc);
+        balance.insert(stop, WorkEntry::from(DurationDef::from(Duration::hours(2))));
+        assert_eq!(balance.calculate_overhours(), Some(Duration::hours(2)));
+
+        assert_eq!(balance.migrate_days_off(), 3);
+        assert_eq!(balance.migrate_days_off(), 0);
+        let cfg = balance.config.as_ref().unwrap();
+        assert!(cfg.holidays.is_empty() && cfg.vacations.is_empty());
+        assert_eq!(
+            balance.marked_days(day(1), day(31)),
+            vec![
+                (day(10), DayKind::Sick),
+                (day(12), DayKind::Holiday),
+                (day(14), DayKind::Vacation)
+            ]
+        );
+    }
+
+    #[test]
+    fn avg_start_time() {
+        let mut balance = TimeBalance::new();
+        let at = |d, h, m| local(2022, 1, d, h, m);
+        let now = at(20, 18, 0);
+        assert_eq!(balance.avg_start_time(now, Local), None);
+        let mut work = |start: DateTime<Utc>, hours| {
+            let entry = WorkEntry::from(DurationDef::from(Duration::hours(hours)));
+            balance.insert(start + Duration::hours(hours), entry);
+        };
+        work(at(3, 7, 2), 8);
+        work(at(17, 8, 0), 4);
+        work(at(17, 13, 0), 4);
+        work(at(18, 9, 30), 8);
+        work(at(19, 9, 0), 8);
+        // still running at now
+        work(at(20, 12, 0), 8);
+        let clock = |h, m| NaiveTime::from_hms_opt(h, m, 0);
+        assert_eq!(balance.avg_start_time(now, Local), clock(8, 23));
+
+        balance.config = Some(Config {
+            avg_window_days: Some(7),
+            ..Default::default()
+        });
+        assert_eq!(balance.avg_start_time(now, Local), clock(8, 50));
+        balance.config = Some(Config {
+            avg_window_days: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(balance.avg_start_time(now, Local), clock(8, 23));
+    }
+
+    #[test]
+    fn avg_start_time_in_local_time() {
+        let mut balance = TimeBalance::new();
+        let tz = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        let now = Utc.with_ymd_and_hms(2022, 1, 20, 18, 0, 0).unwrap();
+        // 08:00 in UTC+2 is 06:00 UTC
+        for day in [10, 11, 12] {
+            let start = Utc.with_ymd_and_hms(2022, 1, day, 6, 0, 0).unwrap();
+            let entry = WorkEntry::from(DurationDef::from(Duration::hours(8)));
+            balance.insert(start + Duration::hours(8), entry);
+        }
+        let eight = NaiveTime::from_hms_opt(8, 0, 0);
+        assert_eq!(balance.avg_start_time(now, tz), eight);
+        assert_eq!(
+            balance.avg_start_time(now, Utc),
+            NaiveTime::from_hms_opt(6, 0, 0)
+        );
+
+        // 01:00 in UTC+2 is still the day before in UTC
+        let start = Utc.with_ymd_and_hms(2022, 1, 12, 23, 0, 0).unwrap();
+        balance.insert(
+            start + Duration::hours(9),
+            WorkEntry::from(DurationDef::from(Duration::hours(9))),
+        );
+        assert_eq!(
+            balance.avg_start_time(now, tz),
+            NaiveTime::from_hms_opt(6, 15, 0)
+        );
+    }
+
+    #[test]
+    fn localized_names() {
+        let day = NaiveDate::from_ymd_opt(2022, 1, 12).unwrap();
+        let mut cfg = Config::default();
+        assert_eq!(cfg.format_date(day, "%A, %d. %B"), "Wednesday, 12. January");
+        assert_eq!(cfg.month_name(chrono::Month::March), "March");
+
+        cfg.locale = Some("xx_YY".into());
+        assert_eq!(cfg.format_date(day, "%A"), "Wednesday");
+
+        cfg.locale = Some("de-DE".into());
+        if cfg!(feature = "locale") {
+            assert_eq!(cfg.format_date(day, "%A, %d. %B"), "Mittwoch, 12. Januar");
+            assert_eq!(cfg.month_name(chrono::Month::March), "März");
+        } else {
+            assert_eq!(cfg.format_date(day, "%A"), "Wednesday");
+        }
+    }
+
+    #[test]
+    fn span_crosses_midnight() {
+        let stop = Local.with_ymd_and_hms(2022, 1, 13, 2, 0, 0).unwrap();
+        let entry = WorkEntry {
+            duration: Duration::hours(3).into(),
+            breaks: vec![(Utc::now(), Duration::minutes(30).into())],
+            note: None,
+            tags: Vec::new(),
+        };
+        let start = entry.start(stop.with_timezone(&Utc)).with_timezone(&Local);
+        assert_eq!(
+            start,
+            Local.with_ymd_and_hms(2022, 1, 12, 22, 30, 0).unwrap()
+        );
+        assert_eq!(format_span(start, stop), "22:30–13.01. 02:00");
+        let stop = Local.with_ymd_and_hms(2022, 1, 12, 23, 0, 0).unwrap();
+        assert_eq!(format_span(stop - Duration::hours(8), stop), "15:00–23:00");
+    }
+
+    #[test]
+    fn state_at_fixed_now() {
+        let now = Utc::now() - Duration::days(2);
+        let mut balance = TimeBalance::new();
+        balance.start(now - Duration::hours(3), now).unwrap();
+        balance.start_break(now - Duration::hours(1), now).unwrap();
+
+        let (dur, _) = balance.start_state(now).unwrap();
+        assert_eq!(dur, Duration::hours(3));
+        assert_eq!(balance.break_state(now).sum, Duration::hours(1));
+        assert_eq!(
+            balance.break_state(now + Duration::minutes(30)).sum,
+            Duration::minutes(90)
+        );
+        assert!(balance.idle(now).is_none());
+        assert_eq!(
+            balance.idle(now + Duration::days(1)),
+            Some(Duration::hours(27))
+        );
+    }
+
+    #[test]
+    fn tags_filter_and_merge() {
+        let morning = NaiveDate::from_ymd_opt(2022, 1, 12)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc);
+        let tagged_balance = || {
+            let mut balance = TimeBalance::new();
+            let tagged = [
+                vec!["a"],
+                vec![" b ", "a", ""],
+                vec![],
+                vec!["a"],
+                vec!["c"],
+            ];
+            for (i, tags) in tagged.into_iter().enumerate() {
+                let start = morning + Duration::minutes(30 * i as i64);
+                balance.start(start, start).expect("starting works");
+                balance
+                    .stop_tagged(
+                        start + Duration::minutes(10),
+                        None,
+                        tags.into_iter().map(String::from).collect(),
+                        false,
+                    )
+                    .expect("stopping works");
+            }
+            balance
+        };
+        let mut balance = tagged_balance();
+        let tags: Vec<_> = balance
+            .time_account
+            .values()
+            .map(|e| e.tags.clone())
+            .collect();
+        assert_eq!(tags[1], vec!["a", "b"]);
+
+        balance.canocicalize().expect("Works");
+        assert_eq!(balance.time_account.len(), 4);
+        assert_eq!(balance.worked_days(), 1);
+        assert!(balance.anomalies(morning + Duration::days(1)).is_empty());
+
+        let mut a = tagged_balance();
+        a.retain_tagged(&["a".to_string()], false);
+        assert_eq!(a.total_worked(), Duration::minutes(30));
+        let mut b_or_untagged = tagged_balance();
+        b_or_untagged.retain_tagged(&["b".to_string()], true);
+        assert_eq!(b_or_untagged.total_worked(), Duration::minutes(20));
+        balance.retain_tagged(&["a".to_string(), "c".to_string()], false);
+        assert_eq!(balance.total_worked(), Duration::minutes(40));
+    }
+
+    #[test]
+    fn auto_stop_when_overdue() {
+        let at = |h| local(2022, 1, 12, h, 0);
+        let mut balance = TimeBalance::new();
+        balance.start(at(9), at(9)).unwrap();
+        assert_eq!(balance.overdue_auto_stop(at(20)), None);
+        balance.config = Some(Config {
+            auto_stop_at: NaiveTime::from_hms_opt(18, 0, 0),
+            ..Config::default()
+        });
+        assert_eq!(balance.overdue_auto_stop(at(17)), None);
+        assert_eq!(balance.overdue_auto_stop(at(20)), Some(at(18)));
+        // Started after the auto stop time, nothing forgotten.
+        balance.start = Some(at(19));
+        assert_eq!(balance.overdue_auto_stop(at(20)), None);
+    }
+
+    #[test]
+    fn add_break_to_past_work() {
+        let at = |h, m| local(2022, 1, 12, h, m);
+        let mut balance = TimeBalance::new();
+        balance.start(at(9, 0), at(9, 0)).unwrap();
+        balance.stop(at(11, 0), None).unwrap();
+        assert!(balance
+            .add_break(at(12, 0), Duration::minutes(5), false)
+            .is_err());
+        assert!(balance
+            .add_break(at(10, 0), Duration::zero(), false)
+            .is_err());
+        let added = balance
+            .add_break(at(10, 0), Duration::minutes(30), false)
+            .unwrap();
+        assert_eq!(added, (Duration::minutes(30), Duration::minutes(90)));
+        let (stop, entry) = balance.time_account.iter().next().unwrap();
+        assert_eq!(entry.start(*stop), at(9, 0));
+        assert_eq!(entry.break_sum(), Duration::minutes(30));
+        // Ending after the stop or overlapping the first break.
+        assert!(balance
+            .add_break(at(10, 45), Duration::minutes(30), true)
+            .is_err());
+        assert!(balance
+            .add_break(at(10, 15), Duration::minutes(10), true)
+            .is_err());
+        assert!(balance
+            .add_break(at(9, 50), Duration::minutes(20), true)
+            .is_err());
+        let (stop, entry) = balance.time_account.iter().next().unwrap();
+        assert_eq!(entry.start(*stop), at(9, 0));
+        assert_eq!(entry.break_sum(), Duration::minutes(30));
+
+        // A forced break longer than the work is cut to the work.
+        let mut balance = TimeBalance::new();
+        balance.start(at(9, 0), at(9, 0)).unwrap();
+        balance.stop(at(11, 0), None).unwrap();
+        assert!(balance
+            .add_break(at(9, 0), Duration::hours(3), false)
+            .is_err());
+        let added = balance
+            .add_break(at(9, 0), Duration::hours(3), true)
+            .unwrap();
+        assert_eq!(added, (Duration::hours(2), Duration::zero()));
+        let (stop, entry) = balance.time_account.iter().next().unwrap();
+        assert_eq!(entry.start(*stop), at(9, 0));
+        assert_eq!(entry.break_sum(), Duration::hours(2));
+    }
+
+    #[test]
+    fn stop_after_duration() {
+        let at = |h, m| local(2022, 1, 12, h, m);
+        let mut balance = TimeBalance::new();
+        balance.start(at(9, 0), at(9, 0)).unwrap();
+        balance.start_break(at(10, 0), at(10, 0)).unwrap();
+        balance.finish_break(at(10, 30)).unwrap();
+        let err = balance
+            .stop_after(Duration::minutes(20), false, None, Vec::new(), at(12, 0))
+            .expect_err("breaks longer than the work fail");
+        assert!(err.to_string().contains("Your breaks of 00:30h are longer"));
+        let err = balance
+            .stop_after(Duration::hours(3), false, None, Vec::new(), at(11, 0))
+            .expect_err("stopping after now fails");
+        assert!(matches!(
+            err.downcast_ref::<TimeError>(),
+            Some(TimeError::InFuture { action: "stop", .. })
+        ));
+        let worked = balance
+            .stop_after(Duration::hours(3), true, None, Vec::new(), at(12, 30))
+            .unwrap();
+        assert_eq!(worked, Duration::hours(3));
+        let (stop, _) = balance.time_account.iter().next().unwrap();
+        assert_eq!(*stop, at(12, 30));
+    }
+
+    #[test]
+    fn add_break_to_running_work() {
+        let at = |h, m| local(2022, 1, 12, h, m);
+        let mut balance = TimeBalance::new();
+        assert!(balance
+            .add_running_break(at(12, 0), Duration::minutes(30))
+            .is_err());
+        balance.start = Some(at(9, 0));
+        assert!(balance
+            .add_running_break(at(9, 20), Duration::minutes(30))
+            .is_err());
+        assert_eq!(
+            balance
+                .add_running_break(at(12, 0), Duration::minutes(30))
+                .unwrap(),
+            at(11, 30)
+        );
+        assert!(balance
+            .add_running_break(at(12, 10), Duration::minutes(30))
+            .is_err());
+        balance
+            .add_running_break(at(11, 0), Duration::minutes(15))
+            .unwrap();
+        assert_eq!(
+            balance.get_breaks(),
+            vec![
+                (at(10, 45), Duration::minutes(15)),
+                (at(11, 30), Duration::minutes(30))
+            ]
+        );
+        assert_eq!(balance.break_state(at(13, 0)).sum, Duration::minutes(45));
     }
 }
@@ -1,19 +1,76 @@
+use std::ffi::OsStr;
 use std::path::PathBuf;
+use std::str::FromStr;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveTime, Utc};
 pub use clap::Parser;
-use clap::{Args, Subcommand};
+use clap::{
+    builder::{PossibleValue, TypedValueParser},
+    error::ErrorKind,
+    Args, Subcommand,
+};
 use stempel::{
-    delta::{parse_duration, parse_offset, parse_time},
-    month::Month,
+    commands::{
+        config::ConfigOptions, export::ExportFormat, import::ImportFormat, report::ReportFormat,
+    },
+    delta::{
+        offset_from, parse_clock, parse_day, parse_duration, parse_offset_duration,
+        parse_signed_duration, time_on, Day,
+    },
+    errors::Result,
+    month::{self, Month},
+    DayKind,
 };
 
+/// Parses a `Month` and offers all month names as possible values, e.g. for
+/// shell completions.
+#[derive(Debug, Clone)]
+pub struct MonthParser;
+
+impl TypedValueParser for MonthParser {
+    type Value = Month;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        _arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let value = value
+            .to_str()
+            .ok_or_else(|| clap::Error::new(ErrorKind::InvalidUtf8).with_cmd(cmd))?;
+        Month::from_str(value)
+            .map_err(|e| clap::Error::raw(ErrorKind::InvalidValue, e).with_cmd(cmd))
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            month::POSSIBLE_VALUES.iter().map(PossibleValue::new),
+        ))
+    }
+}
+
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
 pub struct Cli {
-    /// Path to storage file.
+    /// Path to storage file, files ending in `.toml` are stored as toml if built
+    /// with the `toml` feature. Overrides
+    /// `STEMPEL_STORAGE`.
     #[arg(short, long)]
     pub storage: Option<PathBuf>,
+    /// Use the storage `stempel-<PROFILE>.json` next to the default storage.
+    #[arg(short, long, conflicts_with = "storage")]
+    pub profile: Option<String>,
+    /// Print what a command would do without writing to the storage.
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+    /// Answer all confirmations with yes instead of asking, e.g. in scripts.
+    #[arg(short, long, visible_alias = "no-confirm", global = true)]
+    pub yes: bool,
+    /// Pretend it is this time in RFC 3339, e.g. `2022-01-12T17:00:00Z`, for
+    /// debugging and tests.
+    #[arg(long, global = true, hide = true)]
+    pub now: Option<DateTime<Utc>>,
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -21,24 +78,212 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Start a working period.
-    Start(Timings),
+    Start {
+        #[command(flatten)]
+        timings: Timings,
+        /// Start without asking even if the last work stopped later.
+        #[arg(long)]
+        force: bool,
+    },
     /// Stop a working period.
-    Stop(Timings),
+    Stop {
+        #[command(flatten)]
+        timings: Timings,
+        /// Note what you did, shown in the daily stats and exports.
+        #[arg(short, long)]
+        note: Option<String>,
+        /// Tag the work, e.g. with the client, to filter the stats by it. Can be
+        /// given multiple times.
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Finish a running break at the stop time instead of failing.
+        #[arg(long)]
+        end_break: bool,
+        /// Stop after this much work since the start instead of at a time, in
+        /// format `HH:MM` or like `3h`. Breaks are subtracted from it.
+        #[arg(short, long, conflicts_with_all = ["time", "offset", "end_break"], value_parser = parse_duration)]
+        duration: Option<chrono::Duration>,
+        /// Don't subtract the breaks from the duration.
+        #[arg(long, requires = "duration")]
+        gross: bool,
+    },
     /// Start or stop a break.
     #[command(subcommand)]
     Break(StartStop),
+    /// Continue the last stopped work as if it was never stopped.
+    Continue,
+    /// Pause the current work, same as `break start`.
+    Pause(Timings),
+    /// Resume work after a pause, same as `break stop`.
+    Resume(Timings),
     /// Cancel the last action (Stop can't be undone).
-    Cancel,
+    Cancel {
+        /// Cancel the start and all breaks at once, even while on a break.
+        #[arg(long)]
+        all: bool,
+    },
+    /// Start working now and stop on Ctrl-C or SIGTERM.
+    Work,
     /// Print statistics about tracked time.
     Stats {
         /// Month of which the stats are shown.
+        #[arg(value_parser = MonthParser)]
         month: Option<Month>,
+        /// Print the stats as json, durations are given in seconds.
+        #[arg(long)]
+        json: bool,
+        /// Only print the total of all tracked work.
+        #[arg(long, conflicts_with = "json")]
+        total: bool,
+        /// Print the total of each day in addition to the weeks.
+        #[arg(long)]
+        daily: bool,
+        /// Only print the entries and total of the current week.
+        #[arg(long, conflicts_with_all = ["month", "json", "total", "daily"])]
+        week: bool,
+        /// First day of a range to print instead of months, e.g. `YYYY-MM-DD`,
+        /// `DD.MM.YYYY`, `yesterday` or a weekday.
+        #[arg(long, conflicts_with_all = ["month", "json", "total", "daily", "week"], value_parser = parse_day)]
+        since: Option<Day>,
+        /// Last day of the range in the same formats, defaults to today.
+        #[arg(long, requires = "since", value_parser = parse_day)]
+        until: Option<Day>,
+        /// Number of past months to print instead of the configured one.
+        #[arg(long, conflicts_with_all = ["month", "week", "since", "total"])]
+        months: Option<u8>,
+        /// Group by the configured pay periods instead of calendar months.
+        #[arg(long, conflicts_with_all = ["month", "json", "total", "week", "since"])]
+        periods: bool,
+        /// Only count work with this tag, can be given multiple times.
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Count untagged work, alone or in addition to `--tag`.
+        #[arg(long)]
+        untagged: bool,
+    },
+    /// Print or adjust the overtime, e.g. after taking time off from it.
+    Overtime {
+        /// Signed duration in format `HH:MM` or like `1h30m` to add to the overtime.
+        #[arg(long, allow_hyphen_values = true, value_parser = parse_signed_duration)]
+        adjust: Option<chrono::Duration>,
+        /// Print all adjustments.
+        #[arg(long)]
+        history: bool,
+    },
+    /// Merge all entries of the same day.
+    Canonicalize,
+    /// Report implausible entries in the storage.
+    Doctor {
+        /// Drop impossible entries and merge entries of the same day.
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Export the entries of a month or a range of days to stdout.
+    Export {
+        /// Format of the export.
+        #[arg(long, value_enum, default_value = "md")]
+        format: ExportFormat,
+        /// Month to export, defaults to the current month for Markdown and to
+        /// all entries for csv.
+        #[arg(long, value_parser = MonthParser)]
+        month: Option<Month>,
+        /// First day to export, e.g. `YYYY-MM-DD`, defaults to the first entry.
+        #[arg(long, conflicts_with = "month", value_parser = parse_day)]
+        since: Option<Day>,
+        /// Last day to export, defaults to the last entry.
+        #[arg(long, conflicts_with = "month", value_parser = parse_day)]
+        until: Option<Day>,
+    },
+    /// Render a report of a month, e.g. to attach it to an invoice.
+    Report {
+        /// Format of the report.
+        #[arg(long, value_enum, default_value = "html")]
+        format: ReportFormat,
+        /// Month of the report, defaults to the current month.
+        #[arg(long, value_parser = MonthParser)]
+        month: Option<Month>,
+        /// File to write the report to, defaults to stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Import work from another tool, merging entries of the same day.
+    Import {
+        /// Format of the input.
+        #[arg(long, value_enum, default_value = "csv")]
+        format: ImportFormat,
+        /// File to read the work from.
+        #[arg(long, short)]
+        input: PathBuf,
     },
-    /// Migrate json storage from old to new format, creates backup file `*.bak` overwriting the
-    /// original.
-    Migrate,
-    /// Configure how stempel displays things.
-    Configure,
+    /// Print a Markdown summary to paste into a mail.
+    Digest {
+        /// ISO week of the current year to summarize, defaults to the current week.
+        #[arg(long)]
+        week: Option<u32>,
+    },
+    /// Warn if the daily working hours are exceeded, e.g. from a timer.
+    Check,
+    /// Print the time worked today and the time remaining.
+    Today,
+    /// Print a short summary of yesterday and today, e.g. for a standup.
+    Standup,
+    /// List the breaks of one day.
+    Breaks {
+        /// Day like `YYYY-MM-DD`, `DD.MM.YYYY`, `yesterday` or a weekday,
+        /// defaults to today.
+        #[arg(value_parser = parse_day)]
+        date: Option<Day>,
+    },
+    /// Mark a day as sick day, vacation or holiday, it counts as meeting the
+    /// daily working hours.
+    Mark {
+        /// Day like `YYYY-MM-DD`, `DD.MM.YYYY`, `today`, `yesterday` or a weekday.
+        #[arg(value_parser = parse_day)]
+        date: Day,
+        /// Kind of the day off.
+        #[arg(value_enum)]
+        kind: DayKind,
+    },
+    /// Mark a day as public holiday, it counts as meeting the daily working hours.
+    Holiday {
+        /// Day like `YYYY-MM-DD`, `DD.MM.YYYY`, `today`, `yesterday` or a weekday.
+        #[arg(value_parser = parse_day)]
+        date: Day,
+    },
+    /// Mark a day as vacation, it counts as meeting the daily working hours.
+    Vacation {
+        /// Day like `YYYY-MM-DD`, `DD.MM.YYYY`, `today`, `yesterday` or a weekday.
+        #[arg(value_parser = parse_day)]
+        date: Day,
+    },
+    /// Migrate json storage from old to new format, keeps the original as backup
+    /// `<storage>.bak` or as `<dir>/<name>.<YYYYmmdd-HHMMSS>.bak` with `--backup-dir`.
+    Migrate {
+        /// Write the backup with a timestamp to this directory and keep all
+        /// later backups there.
+        #[arg(long)]
+        backup_dir: Option<PathBuf>,
+    },
+    /// Restore the storage from its most recent backup.
+    Restore,
+    /// List the available profiles.
+    Profiles {
+        /// Use this profile if no `--profile` is given.
+        #[arg(long)]
+        set_default: Option<String>,
+        /// Use the default storage if no `--profile` is given.
+        #[arg(long, conflicts_with = "set_default")]
+        clear_default: bool,
+    },
+    /// Print the storage in use, where it came from, its size and last modification.
+    Whereis,
+    /// Create the storage, asking for your name and the configuration.
+    Init,
+    /// Configure how stempel displays things, interactively if no option is given.
+    Configure(ConfigOptions),
+    /// Inspect the configuration.
+    #[command(subcommand)]
+    Config(ConfigCommand),
     /// Print shell completions.
     Completions {
         #[clap(long)]
@@ -47,13 +292,50 @@ pub enum Commands {
     },
 }
 
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the settings differing from the defaults.
+    Show {
+        /// Print json, e.g. for scripts.
+        #[arg(long)]
+        json: bool,
+        /// Print all settings with the defaults filled in.
+        #[arg(long)]
+        defaults: bool,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 pub enum StartStop {
     /// Start a break, either now or based on flags.
     Start(Timings),
     /// Stop a break, either now or based on flags.
     Stop(Timings),
-    /// A duration of a break in format `HH:MM`.
+    /// List the running and the finished breaks of today.
+    List,
+    /// Remove a finished break of the running work.
+    Cancel {
+        /// Start of the break in format `HH:MM` or `H:MMpm`.
+        #[arg(value_parser = parse_clock)]
+        time: NaiveTime,
+    },
+    /// Add a forgotten break to the work of a past day, or to the running work
+    /// ending now if no day is given.
+    Add {
+        /// Day like `YYYY-MM-DD`, `DD.MM.YYYY`, `today`, `yesterday` or a weekday.
+        #[arg(value_parser = parse_day, requires = "time")]
+        date: Option<Day>,
+        /// Start of the break in format `HH:MM` or `H:MMpm`.
+        #[arg(value_parser = parse_clock)]
+        time: Option<NaiveTime>,
+        /// Length of the break in format `HH:MM` or like `1h30m`.
+        #[arg(short, long, value_parser = parse_duration)]
+        duration: chrono::Duration,
+        /// Cut a break longer than the work to the work instead of failing.
+        #[arg(long)]
+        force: bool,
+    },
+    /// A duration of a break in format `HH:MM` or like `1h30m`.
     #[command(alias = "dur")]
     Duration {
         #[arg(value_parser = parse_duration)]
@@ -63,26 +345,44 @@ pub enum StartStop {
 
 #[derive(Debug, Args, Clone)]
 pub struct Timings {
-    /// Offset to current time in format `XX[h|m|s][+-]`.
-    #[arg(short, long, conflicts_with = "time", value_parser = parse_offset, default_value = "0s+")]
-    offset: DateTime<Utc>,
-    /// An actual timepoint for starting or stopping an action in format `HH:MM`
-    #[arg(short, long, conflicts_with = "offset", value_parser = parse_time)]
-    time: Option<DateTime<Utc>>,
+    /// Offset to current time in format `XX[h|m|s][+-]`, defaults to now.
+    #[arg(short, long, conflicts_with = "time", value_parser = parse_offset_duration)]
+    offset: Option<chrono::Duration>,
+    /// An actual timepoint for starting or stopping an action in format `HH:MM` or `H:MMpm`
+    #[arg(short, long, conflicts_with = "offset", value_parser = parse_clock)]
+    time: Option<NaiveTime>,
 }
 
-#[derive(Debug, Args, Clone)]
-pub struct BreakTypes {
-    /// Offset to current time in format `XX[h|m|s][+-]`.
-    #[arg(short, long, conflicts_with = "time", value_parser = parse_offset, default_value = "0s+")]
-    offset: DateTime<Utc>,
-    /// An actual timepoint for starting or stopping an action in format `HH:MM`.
-    #[arg(short, long, conflicts_with = "offset", value_parser = parse_time)]
-    time: Option<DateTime<Utc>>,
+impl Timings {
+    /// Time point of these timings relative to `now`.
+    pub fn time(&self, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+        match self.time {
+            Some(t) => time_on(now, t),
+            None => offset_from(now, self.offset.unwrap_or_else(chrono::Duration::zero)),
+        }
+    }
+
+    /// Whether a time or an offset was given instead of defaulting to now.
+    pub fn is_explicit(&self) -> bool {
+        self.time.is_some() || self.offset.is_some()
+    }
 }
 
-impl Timings {
-    pub fn time(&self) -> DateTime<Utc> {
-        self.time.unwrap_or(self.offset)
+#[test]
+fn verify_cli() {
+    use clap::CommandFactory;
+    Cli::command().debug_assert();
+}
+
+#[test]
+fn completions_contain_months() {
+    use clap::CommandFactory;
+    for shell in [clap_complete::Shell::Bash, clap_complete::Shell::Zsh] {
+        let mut buf = Vec::new();
+        clap_complete::generate(shell, &mut Cli::command(), "stempel", &mut buf);
+        let script = String::from_utf8(buf).expect("Script is utf8");
+        assert!(script.contains("january"), "{} lacks months", shell);
+        assert!(script.contains("december"), "{} lacks months", shell);
+        assert!(script.contains("current"), "{} lacks months", shell);
     }
 }
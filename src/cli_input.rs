@@ -1,8 +1,12 @@
 /// Parsing and printing from and to cli.
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::errors::*;
 
+/// Whether confirmations are answered with yes without asking.
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
 #[derive(Debug)]
 pub enum YesNo {
     Yes,
@@ -23,12 +27,27 @@ impl FromStr for YesNo {
 }
 
 impl YesNo {
+    /// Answer all following decisions with yes without reading stdin, e.g.
+    /// for scripts.
+    pub fn assume_yes(yes: bool) {
+        ASSUME_YES.store(yes, Ordering::Relaxed);
+    }
+
+    /// Read yes or no from stdin until one is given, yes if `assume_yes` is
+    /// set. The end of stdin counts as no.
     pub fn wait_for_decision() -> Result<Self> {
+        if ASSUME_YES.load(Ordering::Relaxed) {
+            println!("y");
+            return Ok(Self::Yes);
+        }
         let yes = loop {
             let mut input = String::new();
-            std::io::stdin()
+            let read = std::io::stdin()
                 .read_line(&mut input)
                 .wrap_err("Failed to read line from stdin")?;
+            if read == 0 {
+                break Self::No;
+            }
             if let Ok(yn) = crate::cli_input::YesNo::from_str(&input) {
                 log::trace!("Parsed {:?}", yn);
                 break yn;
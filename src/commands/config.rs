@@ -3,9 +3,14 @@
 //! Handler for the `config` subcommand.
 
 use crate::errors::*;
-use std::path::Path;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Utc};
+use colored::*;
+use itertools::Itertools;
+use std::path::{Path, PathBuf};
 
-use crate::balance::{Config, TimeBalance};
+use super::control::save;
+use crate::balance::{Config, DayKind, TimeBalance};
+use crate::delta::Day;
 
 impl std::fmt::Display for Config {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -13,13 +18,262 @@ impl std::fmt::Display for Config {
         if let Some(d) = self.daily_hours {
             write!(f, "\nDaily working hours: {}", d)?;
         }
+        if let Some(w) = self.weekly_hours {
+            write!(f, "\nWeekly working hours: {}", w)?;
+        }
+        if let Some(m) = self.initial_overtime_minutes {
+            write!(f, "\nInitial overtime in minutes: {}", m)?;
+        }
+        if let Some(d) = self.tracking_start_date {
+            write!(f, "\nAccrue overtime since: {}", d)?;
+        }
+        if let Some(d) = self.period_start_day {
+            write!(f, "\nPay periods start on day: {}", d)?;
+        }
+        if let Some(w) = self.weekly_stats {
+            write!(f, "\nPrint current week in stats: {}", w)?;
+        }
+        if let Some(a) = self.auto_stop_at {
+            write!(f, "\nOffer to stop at: {}", a.format("%H:%M"))?;
+        }
+        if let Some(s) = self.split_over_midnight {
+            write!(f, "\nSplit work over midnight: {}", s)?;
+        }
+        if let Some(m) = self.merge_same_day {
+            write!(f, "\nMerge entries of the same day: {}", m)?;
+        }
+        if let Some(s) = self.show_seconds {
+            write!(f, "\nShow seconds: {}", s)?;
+        }
+        if let Some(d) = self.decimal_hours {
+            write!(f, "\nShow decimal hours: {}", d)?;
+        }
+        if let Some(r) = self.display_round_quarter {
+            write!(f, "\nRound totals to quarter hours: {}", r)?;
+        }
+        if let Some(c) = self.overtime_cap_hours {
+            write!(f, "\nCap overtime at: {}h", c)?;
+        }
+        if let Some(g) = self.daily_grace_minutes {
+            write!(f, "\nGrace period per day: {}min", g)?;
+        }
+        if let Some(p) = self.cap_warn_percent {
+            write!(f, "\nWarn at share of the overtime cap: {}%", p)?;
+        }
+        if let Some(n) = self.notifications {
+            write!(f, "\nDesktop notifications: {}", n)?;
+        }
+        if let Some(l) = &self.locale {
+            write!(f, "\nLocale: {}", l)?;
+        }
+        if let Some(p) = self.pretty_json {
+            write!(f, "\nIndent the json storage: {}", p)?;
+        }
+        if let Some(p) = &self.default_profile {
+            write!(f, "\nDefault profile: {}", p)?;
+        }
+        if let Some(h) = self.warn_day_hours {
+            write!(f, "\nWarn about days longer than: {}h", h)?;
+        }
+        if let Some(h) = self.warn_break_hours {
+            write!(f, "\nWarn about breaks longer than: {}h", h)?;
+        }
+        if let Some(d) = self.avg_window_days {
+            write!(f, "\nAverage start time over the last: {} days", d)?;
+        }
+        if let Some(h) = self.clock_skew_hours {
+            write!(
+                f,
+                "\nAsk about starts before the last stop by more than: {}h",
+                h
+            )?;
+        }
+        if let Some(h) = self.warn_idle_hours {
+            write!(f, "\nWarn about sessions running longer than: {}h", h)?;
+        }
+        if let Some(r) = self.hourly_rate {
+            write!(f, "\nHourly rate: {:.2}", r)?;
+            if let Some(c) = &self.currency {
+                write!(f, " {}", c)?;
+            }
+        }
+        if let Some(b) = self.backup_count {
+            write!(f, "\nNumber of backups: {}", b)?;
+        }
+        if let Some(d) = &self.backup_dir {
+            write!(f, "\nBackup dir: {}", d.display())?;
+        }
+        if !self.holidays.is_empty() {
+            write!(f, "\nHolidays: {}", self.holidays.iter().join(", "))?;
+        }
+        if !self.vacations.is_empty() {
+            write!(f, "\nVacations: {}", self.vacations.iter().join(", "))?;
+        }
         Ok(())
     }
 }
 
-pub fn configure<P: AsRef<Path>>(storage: P) -> Result<()> {
-    let mut balance = TimeBalance::from_file(&storage, true)?;
-    let cfg = if let Some(cfg) = balance.config {
+/// Settings of the `configure` subcommand, each given one overwrites the
+/// current value.
+#[derive(Debug, Default, PartialEq, clap::Args)]
+pub struct ConfigOptions {
+    /// Number of months to display in the stats.
+    #[arg(long)]
+    pub month_stats: Option<u8>,
+    /// Daily working hours.
+    #[arg(long)]
+    pub daily_hours: Option<u8>,
+    /// Weekly working hours.
+    #[arg(long)]
+    pub weekly_hours: Option<u8>,
+    /// Overtime in minutes before tracking with stempel.
+    #[arg(long, allow_negative_numbers = true)]
+    pub initial_overtime_minutes: Option<i64>,
+    /// Day like `YYYY-MM-DD` or `DD.MM.YYYY` from which on overtime is accrued.
+    #[arg(long, value_parser = crate::delta::parse_day)]
+    pub tracking_start_date: Option<Day>,
+    /// Day of the month on which pay periods start, e.g. `26` for periods
+    /// from the 26th to the 25th.
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=28))]
+    pub period_start_day: Option<u8>,
+    /// Number of backups to keep, 0 disables them.
+    #[arg(long)]
+    pub backup_count: Option<u8>,
+    /// Directory for the backups instead of next to the storage.
+    #[arg(long)]
+    pub backup_dir: Option<PathBuf>,
+    /// Hours after which stopping warns about a long day.
+    #[arg(long)]
+    pub warn_day_hours: Option<u8>,
+    /// Hours after which finishing a break warns about a long break.
+    #[arg(long)]
+    pub warn_break_hours: Option<u8>,
+    /// Hours after which a running session warns about a forgotten stop.
+    #[arg(long)]
+    pub warn_idle_hours: Option<u8>,
+    /// Number of recent days the average start time in the stats covers, 0
+    /// for all days.
+    #[arg(long)]
+    pub avg_window_days: Option<u16>,
+    /// Hours a start may lie before the last stop before asking whether the
+    /// clock went backwards.
+    #[arg(long)]
+    pub clock_skew_hours: Option<u8>,
+    /// Print the days of the current week in the stats.
+    #[arg(long)]
+    pub weekly_stats: Option<bool>,
+    /// Show seconds in durations.
+    #[arg(long)]
+    pub show_seconds: Option<bool>,
+    /// Show durations as decimal hours like `7.50` instead of `07:30h`.
+    #[arg(long)]
+    pub decimal_hours: Option<bool>,
+    /// Round the displayed totals and running work in the stats to quarter hours.
+    #[arg(long)]
+    pub display_round_quarter: Option<bool>,
+    /// Time in format `HH:MM` at which `stats` and `today` offer to stop work
+    /// still running.
+    #[arg(long, value_parser = crate::delta::parse_clock)]
+    pub auto_stop_at: Option<NaiveTime>,
+    /// Split work over midnight into one entry per day.
+    #[arg(long)]
+    pub split_over_midnight: Option<bool>,
+    /// Merge entries of the same day and tags, `false` keeps separate
+    /// sessions like morning and afternoon.
+    #[arg(long)]
+    pub merge_same_day: Option<bool>,
+    /// Hourly rate to compute earnings in the stats.
+    #[arg(long)]
+    pub hourly_rate: Option<f64>,
+    /// Currency of the hourly rate, e.g. `€`.
+    #[arg(long)]
+    pub currency: Option<String>,
+    /// Cap the overtime in both directions at these hours.
+    #[arg(long)]
+    pub overtime_cap_hours: Option<u8>,
+    /// Ignore differences to the daily hours within these minutes per day.
+    #[arg(long)]
+    pub daily_grace_minutes: Option<u8>,
+    /// Warn in the stats once the overtime reaches this percentage of the cap.
+    #[arg(long)]
+    pub cap_warn_percent: Option<u8>,
+    /// Notify on the desktop when `check` finds the daily hours exceeded.
+    #[arg(long)]
+    pub notifications: Option<bool>,
+    /// Locale of weekday and month names, e.g. `de_DE`.
+    #[arg(long)]
+    pub locale: Option<String>,
+    /// Write the json storage indented, e.g. for meaningful diffs.
+    #[arg(long)]
+    pub pretty_json: Option<bool>,
+}
+
+impl ConfigOptions {
+    /// Overwrite the settings of `cfg` given in these options, relative days
+    /// are resolved against `today`.
+    fn apply(self, cfg: Config, today: NaiveDate) -> Result<Config> {
+        let tracking_start_date = match self.tracking_start_date {
+            Some(day) => Some(day.on(today)?),
+            None => cfg.tracking_start_date,
+        };
+        Ok(Config {
+            month_stats: self.month_stats.unwrap_or(cfg.month_stats),
+            daily_hours: self.daily_hours.or(cfg.daily_hours),
+            weekly_hours: self.weekly_hours.or(cfg.weekly_hours),
+            initial_overtime_minutes: self
+                .initial_overtime_minutes
+                .or(cfg.initial_overtime_minutes),
+            tracking_start_date,
+            period_start_day: self.period_start_day.or(cfg.period_start_day),
+            backup_count: self.backup_count.or(cfg.backup_count),
+            backup_dir: self.backup_dir.or(cfg.backup_dir),
+            warn_day_hours: self.warn_day_hours.or(cfg.warn_day_hours),
+            warn_break_hours: self.warn_break_hours.or(cfg.warn_break_hours),
+            warn_idle_hours: self.warn_idle_hours.or(cfg.warn_idle_hours),
+            clock_skew_hours: self.clock_skew_hours.or(cfg.clock_skew_hours),
+            avg_window_days: self.avg_window_days.or(cfg.avg_window_days),
+            weekly_stats: self.weekly_stats.or(cfg.weekly_stats),
+            show_seconds: self.show_seconds.or(cfg.show_seconds),
+            decimal_hours: self.decimal_hours.or(cfg.decimal_hours),
+            display_round_quarter: self.display_round_quarter.or(cfg.display_round_quarter),
+            split_over_midnight: self.split_over_midnight.or(cfg.split_over_midnight),
+            merge_same_day: self.merge_same_day.or(cfg.merge_same_day),
+            auto_stop_at: self.auto_stop_at.or(cfg.auto_stop_at),
+            hourly_rate: self.hourly_rate.or(cfg.hourly_rate),
+            currency: self.currency.or(cfg.currency),
+            pretty_json: self.pretty_json.or(cfg.pretty_json),
+            notifications: self.notifications.or(cfg.notifications),
+            locale: self.locale.or(cfg.locale),
+            overtime_cap_hours: self.overtime_cap_hours.or(cfg.overtime_cap_hours),
+            daily_grace_minutes: self.daily_grace_minutes.or(cfg.daily_grace_minutes),
+            cap_warn_percent: self.cap_warn_percent.or(cfg.cap_warn_percent),
+            ..cfg
+        })
+    }
+}
+
+/// Change the configuration, interactively if no `opts` are given. Relative
+/// days are resolved against the day of `now`, a missing storage is an error
+/// as it's created by `init`.
+///
+/// Handler of the `configure` subcommand.
+pub fn configure<P: AsRef<Path>>(
+    storage: P,
+    opts: ConfigOptions,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    let today = now.with_timezone(&Local).date_naive();
+    if opts != ConfigOptions::default() {
+        let cfg = opts.apply(balance.config.take().unwrap_or_default(), today)?;
+        warn_unsupported_locale(&cfg);
+        println!("New configuration:");
+        println!("{}", cfg);
+        balance.config = Some(cfg);
+        return save(&balance, storage, dry_run);
+    }
+    let cfg = if let Some(cfg) = balance.config.take() {
         println!("Current configuration:");
         println!("{}", cfg);
         cfg
@@ -29,6 +283,97 @@ pub fn configure<P: AsRef<Path>>(storage: P) -> Result<()> {
     };
 
     println!();
+    balance.config = Some(prompt(cfg, balance.first_day(), today)?);
+    save(&balance, storage, dry_run)
+}
+
+/// Warn if the configured locale falls back to English.
+fn warn_unsupported_locale(cfg: &Config) {
+    #[cfg(feature = "locale")]
+    if let Some(l) = cfg
+        .locale
+        .as_ref()
+        .filter(|_| cfg.chrono_locale().is_none())
+    {
+        log::warn!("Unknown locale '{}', names are printed in English.", l);
+    }
+    #[cfg(not(feature = "locale"))]
+    if cfg.locale.is_some() {
+        log::warn!(
+            "{}, names are printed in English.",
+            feature_disabled("locale")
+        );
+    }
+}
+
+/// Print the configuration, only the settings differing from the defaults
+/// unless `defaults` is set. Prints json if `json` is set.
+///
+/// Handler of the `config show` subcommand.
+pub fn show<P: AsRef<Path>>(storage: P, json: bool, defaults: bool) -> Result<()> {
+    let balance = TimeBalance::from_file_or_legacy(&storage, false)?;
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let value = if defaults {
+        serde_json::to_value(cfg.resolved())?
+    } else {
+        let default = serde_json::to_value(Config::default().resolved())?;
+        let mut value = serde_json::to_value(cfg)?;
+        if let (Some(v), Some(d)) = (value.as_object_mut(), default.as_object()) {
+            v.retain(|k, v| !v.is_null() && d.get(k) != Some(v));
+        }
+        value
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&value)?);
+    } else if defaults {
+        println!("{}", cfg.resolved());
+    } else if let Some(settings) = value.as_object().filter(|v| !v.is_empty()) {
+        for (k, v) in settings {
+            println!("{}: {}", k, v);
+        }
+    } else {
+        println!("Everything is left at its default.");
+    }
+    Ok(())
+}
+
+/// Create the storage, asking for the name and the configuration.
+///
+/// Handler of the `init` subcommand.
+pub fn init<P: AsRef<Path>>(storage: P, now: DateTime<Utc>, dry_run: bool) -> Result<()> {
+    if storage.as_ref().exists() {
+        bail!(usage_err!(
+            "Storage '{}' already exists, run 'stempel configure' to change it.",
+            storage.as_ref().display()
+        ));
+    }
+    println!("Welcome to stempel! Enter your name: ");
+    let mut name = String::new();
+    std::io::stdin()
+        .read_line(&mut name)
+        .wrap_err("Failed to read line from stdin")?;
+    let name = name.trim();
+
+    let mut balance = TimeBalance::new();
+    balance.name = (!name.is_empty()).then(|| name.to_string());
+    let today = now.with_timezone(&Local).date_naive();
+    balance.config = Some(prompt(Config::default(), None, today)?);
+    if dry_run {
+        return save(&balance, &storage, dry_run);
+    }
+    balance.to_file(&storage)?;
+    println!(
+        "Created '{}', run 'stempel start' to track your work.",
+        storage.as_ref().display()
+    );
+    Ok(())
+}
+
+/// Ask for each setting on stdin, starting from `cfg`.
+///
+/// `first_day` with tracked work is offered as start date for the overtime,
+/// relative days are resolved against `today`.
+fn prompt(cfg: Config, first_day: Option<NaiveDate>, today: NaiveDate) -> Result<Config> {
     println!("Let's change the configuration. Enter your desired value, leave blank for keeping the current value.");
 
     let mut input = String::new();
@@ -46,6 +391,103 @@ pub fn configure<P: AsRef<Path>>(storage: P) -> Result<()> {
         .wrap_err("Failed to read line from stdin")?;
     let daily_hours = input.trim().parse::<u8>().unwrap_or(daily_hours);
 
+    let weekly_hours = cfg.weekly_hours.unwrap_or_default();
+    println!("    Weekly working hours, 0 for none ({}): ", weekly_hours);
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let weekly_hours = input.trim().parse::<u8>().unwrap_or(weekly_hours);
+
+    let overtime_cap = cfg.overtime_cap_hours.unwrap_or_default();
+    println!("    Cap overtime at hours, 0 for none ({}): ", overtime_cap);
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let overtime_cap = input.trim().parse::<u8>().unwrap_or(overtime_cap);
+
+    let grace = cfg.daily_grace_minutes.unwrap_or_default();
+    println!(
+        "    Grace period per day in minutes, 0 for none ({}): ",
+        grace
+    );
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let grace = input.trim().parse::<u8>().unwrap_or(grace);
+
+    let initial_overtime = cfg.initial_overtime_minutes.unwrap_or_default();
+    println!(
+        "    Initial overtime in minutes, negative for a deficit ({}): ",
+        initial_overtime
+    );
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let initial_overtime = input.trim().parse::<i64>().unwrap_or(initial_overtime);
+
+    let tracking_start = cfg.tracking_start_date;
+    match first_day {
+        Some(d) => println!(
+            "    Accrue overtime since YYYY-MM-DD, 'y' for your first entry on {}, 'n' for always ({:?}): ",
+            d, tracking_start
+        ),
+        None => println!(
+            "    Accrue overtime since YYYY-MM-DD, 'n' for always ({:?}): ",
+            tracking_start
+        ),
+    }
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let tracking_start = match input.trim() {
+        "y" => first_day.or(tracking_start),
+        "n" => None,
+        i => crate::delta::date_on(today, i).ok().or(tracking_start),
+    };
+
+    let backup_count = cfg.backup_count.unwrap_or_default();
+    println!(
+        "    Number of backups to keep, 0 disables them ({}): ",
+        backup_count
+    );
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let backup_count = input.trim().parse::<u8>().unwrap_or(backup_count);
+
+    let warn_day = cfg.warn_day().num_hours() as u8;
+    println!("    Warn about days longer than hours ({}): ", warn_day);
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let warn_day = input.trim().parse::<u8>().unwrap_or(warn_day);
+
+    let warn_break = cfg.warn_break().num_hours() as u8;
+    println!("    Warn about breaks longer than hours ({}): ", warn_break);
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let warn_break = input.trim().parse::<u8>().unwrap_or(warn_break);
+
+    let warn_idle = cfg.warn_idle().num_hours() as u8;
+    println!(
+        "    Warn about sessions running longer than hours ({}): ",
+        warn_idle
+    );
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let warn_idle = input.trim().parse::<u8>().unwrap_or(warn_idle);
+
     let weekly_stats = cfg.weekly_stats.unwrap_or_default();
     println!("    Print daily stats [y/n]: ({})", weekly_stats);
     input.clear();
@@ -54,19 +496,190 @@ pub fn configure<P: AsRef<Path>>(storage: P) -> Result<()> {
         .wrap_err("Failed to read line from stdin")?;
     let weekly_stats = input.trim().contains('y');
 
+    let rate = cfg.hourly_rate.unwrap_or_default();
+    println!("    Hourly rate, 0 for none ({}): ", rate);
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let rate = input.trim().parse::<f64>().unwrap_or(rate);
+
+    let currency = cfg.currency.clone().unwrap_or_default();
+    println!("    Currency of the hourly rate ({}): ", currency);
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let currency = match input.trim() {
+        "" => currency,
+        c => c.to_string(),
+    };
+
+    let show_seconds = cfg.show_seconds.unwrap_or_default();
+    println!("    Show seconds in durations [y/n]: ({})", show_seconds);
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let show_seconds = match input.trim() {
+        "" => show_seconds,
+        i => i.contains('y'),
+    };
+
+    let round = cfg.display_round_quarter.unwrap_or_default();
+    println!(
+        "    Round totals in stats to quarter hours [y/n]: ({})",
+        round
+    );
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let round = match input.trim() {
+        "" => round,
+        i => i.contains('y'),
+    };
+
+    let split = cfg.split_over_midnight.unwrap_or_default();
+    println!(
+        "    Split work over midnight into two days [y/n]: ({})",
+        split
+    );
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let split = match input.trim() {
+        "" => split,
+        i => i.contains('y'),
+    };
+
+    let pretty = cfg.pretty_json.unwrap_or_default();
+    println!("    Write the json storage indented [y/n]: ({})", pretty);
+    input.clear();
+    std::io::stdin()
+        .read_line(&mut input)
+        .wrap_err("Failed to read line from stdin")?;
+    let pretty = match input.trim() {
+        "" => pretty,
+        i => i.contains('y'),
+    };
+
     let cfg = Config {
         month_stats: month_history,
         daily_hours: Some(daily_hours),
+        weekly_hours: (weekly_hours > 0).then_some(weekly_hours),
+        overtime_cap_hours: (overtime_cap > 0).then_some(overtime_cap),
+        daily_grace_minutes: (grace > 0).then_some(grace),
+        initial_overtime_minutes: Some(initial_overtime),
+        tracking_start_date: tracking_start,
+        backup_count: Some(backup_count),
+        warn_day_hours: Some(warn_day),
+        warn_break_hours: Some(warn_break),
+        warn_idle_hours: Some(warn_idle),
         weekly_stats: Some(weekly_stats),
-        //..cfg
+        split_over_midnight: Some(split),
+        show_seconds: Some(show_seconds),
+        display_round_quarter: Some(round),
+        hourly_rate: (rate > 0.0).then_some(rate),
+        currency: (!currency.is_empty()).then_some(currency),
+        pretty_json: Some(pretty),
+        ..cfg
     };
     log::trace!("Months to display {}", cfg.month_stats);
     log::trace!("Daily working hours {:?}", cfg.daily_hours);
 
-    balance.config = Some(cfg);
+    Ok(cfg)
+}
+
+/// Mark `day` as sick day, vacation or holiday, replacing a previous marker.
+///
+/// Handler of the `mark`, `holiday` and `vacation` subcommands.
+pub fn mark_day<P: AsRef<Path>>(
+    storage: P,
+    day: NaiveDate,
+    kind: DayKind,
+    dry_run: bool,
+) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    if balance.day_marker(day) == Some(kind) {
+        println!("{} is already a {}.", day.format("%d.%m.%Y"), kind);
+        return Ok(());
+    }
+    balance.mark_day(day, kind);
+    let date = balance
+        .config
+        .as_ref()
+        .unwrap_or_default()
+        .format_date(day, "%A, %d.%m.%Y");
+    match kind {
+        DayKind::Sick => println!("Get well soon, {} is marked as sick day.", date),
+        _ => println!("Enjoy your {} on {}.", kind, date),
+    }
+
+    save(&balance, storage, dry_run)
+}
 
-    balance.canocicalize()?;
-    balance.to_file(storage)?;
+/// File name of the default storage.
+pub const DEFAULT_STORAGE: &str = "stempel.json";
+
+/// File name of the storage of `profile`.
+pub fn profile_file(profile: &str) -> String {
+    format!("stempel-{}.json", profile)
+}
 
+/// Default profile configured in the `default_storage`, if any.
+pub fn default_profile<P: AsRef<Path>>(default_storage: P) -> Option<String> {
+    TimeBalance::from_file(default_storage, false)
+        .ok()?
+        .config?
+        .default_profile
+}
+
+/// Set or clear the default profile in the `default_storage`.
+pub fn set_default_profile<P: AsRef<Path>>(
+    default_storage: P,
+    profile: Option<String>,
+    dry_run: bool,
+) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&default_storage, true)?;
+    match &profile {
+        Some(p) => println!("Using profile '{}' by default.", p),
+        None => println!("Using the default storage if no profile is given."),
+    }
+    balance
+        .config
+        .get_or_insert_with(Config::default)
+        .default_profile = profile;
+    save(&balance, default_storage, dry_run)
+}
+
+/// List all profiles found in `dir`, i.e. files named `stempel-<profile>.json`.
+///
+/// Handler of the `profiles` subcommand.
+pub fn profiles<P: AsRef<Path>, Q: AsRef<Path>>(dir: P, default_storage: Q) -> Result<()> {
+    let default = default_profile(default_storage);
+    let profiles: Vec<String> = std::fs::read_dir(&dir)
+        .wrap_err_with(|| format!("Failed to read '{}'", dir.as_ref().display()))?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.strip_prefix("stempel-")?
+                .strip_suffix(".json")
+                .map(|p| p.to_string())
+        })
+        .sorted()
+        .collect();
+
+    if profiles.is_empty() {
+        println!("No profiles yet, use `--profile <name> init` to create one.");
+    }
+    for p in profiles {
+        if Some(&p) == default.as_ref() {
+            println!("{} (default)", p.green());
+        } else {
+            println!("{}", p);
+        }
+    }
     Ok(())
 }
@@ -1,26 +1,71 @@
 //! Handler for the start, stop and break subcommands.
 
-use crate::balance::TimeBalance;
+use crate::balance::{Config, TimeBalance};
+use crate::cli_input::YesNo;
+use crate::WorkTracker;
 
 use crate::errors::*;
-use chrono::{DateTime, Duration, Local, Utc};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, Utc};
 use colored::*;
-use std::{convert::TryFrom, ops::Add, path::Path};
+use std::{
+    convert::TryFrom,
+    ops::Add,
+    path::{Path, PathBuf},
+};
+
+/// Write `balance` to `storage` unless `dry_run` is set.
+pub(super) fn save<P: AsRef<Path>>(balance: &TimeBalance, storage: P, dry_run: bool) -> Result<()> {
+    if dry_run {
+        print_dry_run();
+        Ok(())
+    } else {
+        balance.to_file(storage)
+    }
+}
+
+/// Tell that nothing was written because of `--dry-run`.
+fn print_dry_run() {
+    println!("{}", "Dry run, the storage was not modified.".yellow());
+}
+
+/// Warn if a break of `dur` is longer than configured.
+fn warn_long_break(balance: &TimeBalance, dur: Duration) {
+    if dur > balance.config.as_ref().unwrap_or_default().warn_break() {
+        log::warn!(
+            "Your break took {}:{:02}h, did you fall asleep?",
+            dur.num_hours(),
+            dur.num_minutes() % 60
+        );
+    }
+}
 
 /// Handles the start of a working period and breaks called by subcommand
 /// `start`.
 ///
-/// `storage` points to the json storage file. Creates the database file if it
-/// does not exist. Returns an error if there already exists a start entry in
-/// the storage.
-pub fn start<P: AsRef<Path>>(storage: P, time: DateTime<Utc>) -> Result<()> {
-    let mut balance = TimeBalance::from_file(&storage, true)?;
-    balance.start(time).map_err(|e| {
-        usage_err!(
-            "You already started at {}",
-            e.with_timezone(&Local).time().format("%H:%M")
-        )
-    })?;
+/// `storage` points to the json storage file. Returns an error if there already
+/// exists a start entry in the storage, you're on a break or `time` lies in the
+/// future of `now`. Asks before starting long before the latest entry stopped unless
+/// `force` is set, the clock likely went backwards.
+pub fn start<P: AsRef<Path>>(
+    storage: P,
+    time: DateTime<Utc>,
+    now: DateTime<Utc>,
+    force: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let mut tracker = WorkTracker::open(&storage)?;
+    if let Some(latest) = tracker.balance().skewed_start(time).filter(|_| !force) {
+        println!(
+            "Your last work stopped at {}, after this start at {}. Did your clock go backwards? Start anyway? [y/N]",
+            latest.with_timezone(&Local).format("%d.%m.%Y %H:%M"),
+            time.with_timezone(&Local).format("%d.%m.%Y %H:%M")
+        );
+        if let YesNo::No = YesNo::wait_for_decision()? {
+            println!("Nothing started, check your clock or start with '--force'.");
+            return Ok(());
+        }
+    }
+    tracker.start(time, now)?;
     println!(
         "You started at {}, let's go!",
         time.with_timezone(&Local)
@@ -29,50 +74,209 @@ pub fn start<P: AsRef<Path>>(storage: P, time: DateTime<Utc>) -> Result<()> {
             .to_string()
             .green()
     );
-    balance.canocicalize()?;
-    balance.to_file(storage)?;
-
-    Ok(())
+    save(tracker.balance(), storage, dry_run)
 }
 
 /// Calculates and writes the work to the storage based on a previous start.
 ///
-/// `storage` points to the json storage file. Throws an error if there is no
-/// such storage yet.
-pub fn stop<P: AsRef<Path>>(storage: P, time: DateTime<Utc>) -> Result<()> {
-    let mut balance = TimeBalance::from_file(&storage, false)?;
-    let duration = balance.stop(time)?;
+/// `storage` points to the json storage file, the entry is annotated with
+/// `note` if given and tagged with `tags`. A running break is finished at `time` if `end_break` is
+/// set, otherwise it's an error like a missing storage. Work started on an
+/// earlier day is stopped without asking if the `time` is `explicit`.
+pub fn stop<P: AsRef<Path>>(
+    storage: P,
+    time: DateTime<Utc>,
+    note: Option<String>,
+    tags: Vec<String>,
+    end_break: bool,
+    explicit: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let mut tracker = WorkTracker::open(&storage)?;
+    if end_break && tracker.state_at(time).on_break_since.is_some() {
+        let dur = tracker.finish_break(time)?;
+        warn_long_break(tracker.balance(), dur);
+        println!(
+            "Finished your break of {}.",
+            tracker
+                .balance()
+                .config
+                .as_ref()
+                .unwrap_or_default()
+                .format_duration(dur)
+        );
+    }
+    let duration = tracker.stop_tagged(time, note, tags, explicit)?;
+    report_stop(tracker.balance(), duration);
+    save(tracker.balance(), storage, dry_run)
+}
+
+/// Writes the work to the storage after `dur` of work since the start instead
+/// of at a point in time, it's an error if that lies after `now`. The recorded
+/// breaks are subtracted from `dur` unless `gross` is set.
+///
+/// Handler of `stop --duration`, `note` and `tags` like for `stop`.
+pub fn stop_after<P: AsRef<Path>>(
+    storage: P,
+    dur: Duration,
+    gross: bool,
+    note: Option<String>,
+    tags: Vec<String>,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<()> {
+    let mut tracker = WorkTracker::open(&storage)?;
+    let duration = tracker.stop_after(dur, gross, note, tags, now)?;
+    report_stop(tracker.balance(), duration);
+    save(tracker.balance(), storage, dry_run)
+}
+
+/// Print the just stopped work of `duration`, warn if it's suspiciously long.
+fn report_stop(balance: &TimeBalance, duration: Duration) {
+    if duration > balance.config.as_ref().unwrap_or_default().warn_day() {
+        log::warn!(
+            "You worked more than {}h, did you forget to stop?",
+            duration.num_hours()
+        );
+    }
     println!(
         "You worked {}:{:02}h today. Enjoy your evening \u{1F389}",
         duration.num_hours(),
         duration.num_minutes() % 60
     );
-    balance.canocicalize()?;
-    balance.to_file(&storage)?;
+}
 
-    Ok(())
+/// Start working at `now`, block until Ctrl-C or SIGTERM and stop then.
+///
+/// Handler of the `work` subcommand. The storage is read again on stop so
+/// breaks taken in the meantime from another terminal are kept, a running
+/// break is finished. The stop is known, so work over midnight is stopped
+/// without asking.
+pub fn work<P: AsRef<Path>>(storage: P, now: DateTime<Utc>, dry_run: bool) -> Result<()> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = tx.send(());
+    })
+    .wrap_err("Failed to install the signal handler")?;
+
+    start(&storage, now, now, false, dry_run)?;
+    let running = std::time::Instant::now();
+    println!("Press Ctrl-C to stop.");
+    rx.recv().wrap_err("Failed to wait for a signal")?;
+    println!();
+    if dry_run {
+        return Ok(());
+    }
+    let stop_time = now + Duration::from_std(running.elapsed()).wrap_err("Worked for too long")?;
+    stop(storage, stop_time, None, Vec::new(), true, true, dry_run)
+}
+
+/// Continue the most recently stopped work as if it was never stopped.
+///
+/// Handler of the `continue` subcommand. Throws an error if work is already
+/// started or there is nothing to continue.
+pub fn continue_work<P: AsRef<Path>>(storage: P, dry_run: bool) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    let worked = balance.continue_last()?;
+    println!(
+        "You already worked {}:{:02}h, keep going!",
+        worked.num_hours(),
+        worked.num_minutes() % 60
+    );
+    save(&balance, storage, dry_run)
 }
 
 /// Cancels a break if present, otherwise the start or throws an error. Handler
 /// of the `cancel` subcommand.
 ///
-/// `storage` is the path pointing to the database file.
-pub fn cancel<P: AsRef<Path>>(storage: P) -> Result<()> {
+/// `storage` is the path pointing to the database file. If `all` is set, the
+/// start and all its breaks are canceled at once, even while on a break.
+pub fn cancel<P: AsRef<Path>>(storage: P, all: bool, dry_run: bool) -> Result<()> {
     let mut balance = TimeBalance::from_file(&storage, false)?;
-    balance.cancel()?;
+    if all {
+        balance.cancel_all()?;
+    } else {
+        balance.cancel()?;
+    }
     balance.canocicalize()?;
-    balance.to_file(&storage)?;
     println!("Canceled last action.");
-    Ok(())
+    save(&balance, storage, dry_run)
+}
+
+/// Remove the finished break of the running work started at `time`.
+///
+/// Handler of the `break cancel` subcommand.
+pub fn cancel_break<P: AsRef<Path>>(storage: P, time: NaiveTime, dry_run: bool) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    let dur = balance.cancel_break_at(time)?;
+    println!(
+        "Canceled the break at {} of {}.",
+        time.format("%H:%M"),
+        balance
+            .config
+            .as_ref()
+            .unwrap_or_default()
+            .format_duration(dur)
+    );
+    save(&balance, storage, dry_run)
+}
+
+/// Add a forgotten break of `dur` at the time on the date in `at` to the work
+/// tracked then, or to the running work ending at `now` if `at` is not given.
+///
+/// Handler of the `break add` subcommand.
+pub fn add_break<P: AsRef<Path>>(
+    storage: P,
+    at: Option<(NaiveDate, NaiveTime)>,
+    dur: Duration,
+    force: bool,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    let Some((date, time)) = at else {
+        let start = balance.add_running_break(now, dur)?;
+        println!(
+            "Added a break of {} since {}.",
+            balance
+                .config
+                .as_ref()
+                .unwrap_or_default()
+                .format_duration(dur),
+            start.with_timezone(&Local).format("%H:%M")
+        );
+        return save(&balance, storage, dry_run);
+    };
+    let start = date
+        .and_time(time)
+        .and_local_timezone(Local)
+        .earliest()
+        .ok_or_else(|| usage_err!("{} doesn't exist on {}", time.format("%H:%M"), date))?
+        .with_timezone(&Utc);
+    let (dur, worked) = balance.add_break(start, dur, force)?;
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    println!(
+        "Added a break of {} at {}, {} worked remain.",
+        cfg.format_duration(dur),
+        time.format("%H:%M"),
+        cfg.format_duration(worked)
+    );
+    save(&balance, storage, dry_run)
 }
 
 /// Stop a 'break', calculates the duration and writes it to the database.
 ///
 /// Handler of `break stop` subcommand. `storage` is the json storage file.
 /// Throws an error if there is no stared break in the database.
-pub fn stop_break<P: AsRef<Path>>(storage: P, time: DateTime<Utc>, verbose: bool) -> Result<()> {
+pub fn stop_break<P: AsRef<Path>>(
+    storage: P,
+    time: DateTime<Utc>,
+    verbose: bool,
+    dry_run: bool,
+) -> Result<()> {
     let mut balance = TimeBalance::from_file(&storage, false)?;
     let dur = balance.finish_break(time)?;
+    warn_long_break(&balance, dur);
     if verbose {
         println!(
             "You had a break for {}:{:02}h. Way to go!",
@@ -80,17 +284,22 @@ pub fn stop_break<P: AsRef<Path>>(storage: P, time: DateTime<Utc>, verbose: bool
             dur.num_minutes() % 60
         );
     }
-    balance.to_file(&storage)?;
-    Ok(())
+    save(&balance, storage, dry_run)
 }
 
 /// Start a 'break' by adding a `break` entry to the database.
 ///
 /// Handler of the `break start` subcommand. `storage` is the database file.
 /// Throws an error if there is no start entry in the database.
-pub fn start_break<P: AsRef<Path>>(storage: P, time: DateTime<Utc>, verbose: bool) -> Result<()> {
+pub fn start_break<P: AsRef<Path>>(
+    storage: P,
+    time: DateTime<Utc>,
+    now: DateTime<Utc>,
+    verbose: bool,
+    dry_run: bool,
+) -> Result<()> {
     let mut balance = TimeBalance::from_file(&storage, false)?;
-    let dur = balance.start_break(time)?;
+    let dur = balance.start_break(time, now)?;
     if verbose {
         println!(
             "Started a break after working {}:{:02}h.",
@@ -98,30 +307,295 @@ pub fn start_break<P: AsRef<Path>>(storage: P, time: DateTime<Utc>, verbose: boo
             dur.num_minutes() % 60
         );
     }
-    balance.to_file(storage)?;
-    Ok(())
+    save(&balance, storage, dry_run)
+}
+
+/// Pause the current work, handler of the `pause` subcommand.
+///
+/// Same as `break start` but with a message tailored to pausing.
+pub fn pause<P: AsRef<Path>>(
+    storage: P,
+    time: DateTime<Utc>,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    let dur = balance.start_break(time, now)?;
+    println!(
+        "Paused at {} after working {}:{:02}h.",
+        time.with_timezone(&Local)
+            .time()
+            .format("%H:%M")
+            .to_string()
+            .yellow(),
+        dur.num_hours(),
+        dur.num_minutes() % 60
+    );
+    save(&balance, storage, dry_run)
 }
 
-/// Add a full 'break' by adding a `break` entry to the database of length `duration`.
+/// Resume the paused work, handler of the `resume` subcommand.
+///
+/// Same as `break stop` but with a message tailored to resuming.
+pub fn resume<P: AsRef<Path>>(storage: P, time: DateTime<Utc>, dry_run: bool) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    let dur = balance.finish_break(time)?;
+    warn_long_break(&balance, dur);
+    println!(
+        "Resumed at {} after a pause of {}:{:02}h.",
+        time.with_timezone(&Local)
+            .time()
+            .format("%H:%M")
+            .to_string()
+            .green(),
+        dur.num_hours(),
+        dur.num_minutes() % 60
+    );
+    save(&balance, storage, dry_run)
+}
+
+/// Add a full 'break' by adding a `break` entry to the database of length
+/// `duration` starting at `now`.
 ///
 /// Handler of the `break dur` subcommand. `storage` is the database file.
-pub fn take_break<P: AsRef<Path>>(storage: P, duration: Duration) -> Result<()> {
-    let now = chrono::Utc::now();
+pub fn take_break<P: AsRef<Path>>(
+    storage: P,
+    duration: Duration,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<()> {
     println!(
         "Taking a break for {}:{}h.",
         duration.num_hours(),
         duration.num_minutes() % 60
     );
-    start_break(&storage, now, false)?;
-    let end = now.add(duration);
-    stop_break(&storage, end, false)
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    balance.start_break(now, now)?;
+    balance.finish_break(now.add(duration))?;
+    save(&balance, storage, dry_run)
 }
 
-pub fn migrate<P: AsRef<Path>>(path: P) -> Result<()> {
+/// Migrate the storage at `path` from the legacy format, the legacy storage
+/// is kept as backup, in `backup_dir` if given which is configured for later
+/// backups, too. In a storage already in the current format, only holidays
+/// and vacations in the configuration are moved to day markers. Nothing is
+/// written if `dry_run` is set.
+///
+/// Handler of the `migrate` subcommand.
+pub fn migrate<P: AsRef<Path>>(path: P, backup_dir: Option<PathBuf>, dry_run: bool) -> Result<()> {
+    if let Some(mut balance) = path
+        .as_ref()
+        .is_file()
+        .then(|| TimeBalance::from_file(&path, false).ok())
+        .flatten()
+    {
+        match balance.migrate_days_off() {
+            0 => println!(
+                "'{}' is already in the current format, nothing to migrate.",
+                path.as_ref().display()
+            ),
+            n => {
+                println!("Moved {} holidays and vacations to day markers.", n);
+                save(&balance, &path, dry_run)?;
+            }
+        }
+        return Ok(());
+    }
     let storage = crate::storage::WorkStorage::from_file(&path)?;
-    let balance = TimeBalance::try_from(&storage)?;
+    let mut balance = TimeBalance::try_from(&storage)?;
+    if backup_dir.is_some() {
+        balance.config = Some(Config {
+            backup_dir: backup_dir.clone(),
+            ..Config::default()
+        });
+    }
+    let backup = crate::backup::migration_backup(&path, backup_dir.as_deref(), Local::now());
+    if dry_run {
+        println!(
+            "Would migrate and keep the old storage in '{}'.",
+            backup.display()
+        );
+        print_dry_run();
+        return Ok(());
+    }
+    if let Some(dir) = &backup_dir {
+        std::fs::create_dir_all(dir)
+            .wrap_err_with(|| format!("Failed to create backup dir '{}'", dir.display()))?;
+    }
+    storage.write(&backup)?;
     balance.to_file(&path)?;
-    let migrated_path: String = (path.as_ref().to_string_lossy() + ".bak").to_string();
-    storage.write(std::path::PathBuf::from(migrated_path))?;
+    println!(
+        "Migrated, the old storage is kept in '{}'.",
+        backup.display()
+    );
+    Ok(())
+}
+
+/// Record a manual change `adjust` of the overtime, print the recorded
+/// changes if `history` is set and the resulting overtime. Changes are
+/// recorded at `now`.
+///
+/// Handler of the `overtime` subcommand.
+pub fn overtime<P: AsRef<Path>>(
+    storage: P,
+    adjust: Option<Duration>,
+    history: bool,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    if history {
+        let mut adjustments = balance.adjustments().peekable();
+        if adjustments.peek().is_none() {
+            println!("No adjustments of the overtime.");
+        }
+        for (time, dur) in adjustments {
+            println!(
+                "{}  {:>8}",
+                time.with_timezone(&Local).format("%d.%m.%Y %H:%M"),
+                cfg.format_duration(dur)
+            );
+        }
+    }
+    if let Some(dur) = adjust {
+        println!("Adjusted your overtime by {}.", cfg.format_duration(dur));
+        balance.adjust_overtime(now, dur);
+    }
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    match balance.calculate_overhours() {
+        Some(hours) => println!("Your overtime is {}.", cfg.format_duration(hours)),
+        None if adjust.is_none() && !history => bail!(daily_hours_unset()),
+        None => println!("{}.", daily_hours_unset()),
+    }
+    if adjust.is_some() {
+        save(&balance, storage, dry_run)?;
+    }
+    Ok(())
+}
+
+/// Merge all entries of the same day after confirming the planned merges,
+/// only the plan is printed if `dry_run` is set.
+///
+/// Handler of the `canonicalize` subcommand.
+pub fn canonicalize<P: AsRef<Path>>(storage: P, dry_run: bool) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    let planned = balance.planned_merges();
+    if !balance
+        .config
+        .as_ref()
+        .unwrap_or_default()
+        .merges_same_day()
+    {
+        println!("Nothing to merge, merging same-day entries is disabled in the config.");
+        return Ok(());
+    }
+    if planned.is_empty() {
+        println!("Nothing to merge, every day has a single entry.");
+        return Ok(());
+    }
+    for (day, count) in &planned {
+        println!("{}: merge {} entries", day.format("%d.%m.%Y"), count);
+    }
+    if dry_run {
+        println!("{}", "Dry run, the storage was not modified.".yellow());
+        return Ok(());
+    }
+    println!("Merge these entries? [y/N]");
+    if let YesNo::No = YesNo::wait_for_decision()? {
+        println!("Nothing merged.");
+        return Ok(());
+    }
+    balance.canocicalize()?;
+    let merged: usize = planned.iter().map(|(_, n)| n - 1).sum();
+    println!(
+        "Merged {} entries, {} days remain.",
+        merged,
+        balance.worked_days()
+    );
+    save(&balance, storage, dry_run)
+}
+
+/// Report implausible entries in the storage, drop impossible ones and merge
+/// entries of the same day if `fix` is set. Entries after `now` are in the
+/// future.
+///
+/// Handler of the `doctor` subcommand.
+pub fn doctor<P: AsRef<Path>>(
+    storage: P,
+    fix: bool,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    let anomalies = balance.anomalies(now);
+    if anomalies.is_empty() {
+        println!("{}", "No anomalies found.".green());
+        return Ok(());
+    }
+    for (k, a) in &anomalies {
+        println!(
+            "{} Entry stopped at {} {}.",
+            "Warning:".yellow(),
+            k.with_timezone(&Local).format("%d.%m.%Y %H:%M"),
+            a
+        );
+    }
+    let merges = balance
+        .config
+        .as_ref()
+        .unwrap_or_default()
+        .merges_same_day();
+    if !fix {
+        if merges {
+            println!("Run with '--fix' to drop impossible entries and merge same-day entries.");
+        } else {
+            println!("Run with '--fix' to drop impossible entries.");
+        }
+        return Ok(());
+    }
+    let dropped = balance.fix_anomalies(now)?;
+    if merges {
+        println!("Dropped {} entries and merged same-day entries.", dropped);
+    } else {
+        println!("Dropped {} entries.", dropped);
+    }
+    save(&balance, storage, dry_run)
+}
+
+/// Overwrite the storage with its most recent backup after confirmation, only
+/// name the backup if `dry_run` is set.
+///
+/// Handler of the `restore` subcommand.
+pub fn restore<P: AsRef<Path>>(storage: P, dry_run: bool) -> Result<()> {
+    let storage = storage.as_ref();
+    // The backup dir is unknown if the storage can't be read anymore.
+    let dir = TimeBalance::from_file(storage, false)
+        .ok()
+        .and_then(|b| b.config?.backup_dir);
+    let backup = crate::backup::latest(storage, dir.as_deref())
+        .ok_or_else(|| usage_err!("There is no backup of '{}'", storage.display()))?;
+    if dry_run {
+        println!(
+            "Would overwrite '{}' with the backup '{}'.",
+            storage.display(),
+            backup.display()
+        );
+        print_dry_run();
+        return Ok(());
+    }
+    println!(
+        "Do you really want to overwrite '{}' with the backup '{}'? [y/N]",
+        storage.display(),
+        backup.display()
+    );
+    match YesNo::wait_for_decision()? {
+        YesNo::Yes => {
+            std::fs::copy(&backup, storage)
+                .wrap_err_with(|| format!("Failed to restore backup '{}'", backup.display()))?;
+            println!("Restored '{}'.", storage.display());
+        }
+        YesNo::No => println!("Nothing restored."),
+    }
     Ok(())
 }
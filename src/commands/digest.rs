@@ -0,0 +1,134 @@
+//! Handler for the digest subcommand.
+//!
+//! Summarizes the work of one week as short Markdown block, e.g. to paste it
+//! into a mail to the team lead.
+
+use crate::balance::TimeBalance;
+use crate::errors::*;
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+
+use std::path::Path;
+
+/// Width of the bar of a day with the daily working hours.
+const BAR_WIDTH: i64 = 16;
+
+/// Partial blocks of a bar in eighths of a full block.
+const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+/// Print a Markdown summary of the ISO `week` of the year of `now` to stdout,
+/// the week of `now` if none is given.
+///
+/// Handler of the `digest` subcommand.
+pub fn digest<P: AsRef<Path>>(storage: P, week: Option<u32>, now: DateTime<Utc>) -> Result<()> {
+    let balance = TimeBalance::from_file_or_legacy(&storage, false)?;
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let current = now.with_timezone(&Local).date_naive().iso_week();
+    let monday = NaiveDate::from_isoywd_opt(
+        current.year(),
+        week.unwrap_or(current.week()),
+        chrono::Weekday::Mon,
+    )
+    .ok_or_else(|| {
+        usage_err!(
+            "There is no week {} in {}",
+            week.unwrap_or_default(),
+            current.year()
+        )
+    })?;
+    let week = monday.iso_week();
+    let days = monday
+        .iter_days()
+        .take(7)
+        .map(|day| {
+            let (worked, breaks) = balance
+                .daily_range(day, Local)?
+                .fold((Duration::zero(), Duration::zero()), |(w, b), (_, e)| {
+                    (w + Duration::from(e), b + e.break_sum())
+                });
+            Ok((day, worked, breaks))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    println!(
+        "**Week {}** ({} – {})",
+        week.week(),
+        monday.format("%d.%m.%Y"),
+        (monday + Duration::days(6)).format("%d.%m.%Y")
+    );
+    println!();
+    if balance.week_entries(monday).next().is_none() {
+        println!("No time tracked in this week.");
+        return Ok(());
+    }
+
+    let total = days
+        .iter()
+        .fold(Duration::zero(), |acc, (_, w, _)| acc + *w);
+    let breaks = days
+        .iter()
+        .fold(Duration::zero(), |acc, (_, _, b)| acc + *b);
+    println!("- Total: {}", cfg.format_duration(total));
+    if let Some(delta) = overtime_delta(&balance, &days) {
+        println!("- Overtime: {}", cfg.format_duration(delta));
+    }
+    println!("- Breaks: {}", cfg.format_duration(breaks));
+    println!();
+
+    let scale = match cfg.daily_hours {
+        Some(h) => Duration::hours(h.into()),
+        None => days.iter().map(|(_, w, _)| *w).max().unwrap_or_default(),
+    };
+    println!("```");
+    for (day, worked, _) in &days {
+        println!(
+            "{}  {:<width$}  {}",
+            cfg.format_date(*day, "%a %d.%m."),
+            bar(*worked, scale),
+            cfg.format_duration(*worked),
+            width = BAR_WIDTH as usize
+        );
+    }
+    println!("```");
+    Ok(())
+}
+
+/// Overtime of the worked `days`, like in the monthly stats. `None` if no
+/// daily hours are configured.
+fn overtime_delta(
+    balance: &TimeBalance,
+    days: &[(NaiveDate, Duration, Duration)],
+) -> Option<Duration> {
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let daily = Duration::hours(cfg.daily_hours?.into());
+    Some(
+        days.iter()
+            .filter(|(_, w, _)| !w.is_zero())
+            .filter(|(day, _, _)| cfg.tracking_start_date.is_none_or(|s| *day >= s))
+            .fold(Duration::zero(), |acc, (day, w, _)| {
+                acc + balance.day_overtime(*day, *w, daily)
+            }),
+    )
+}
+
+/// Bar of unicode blocks for `dur`, `BAR_WIDTH` blocks long for `scale`.
+fn bar(dur: Duration, scale: Duration) -> String {
+    if scale <= Duration::zero() || dur <= Duration::zero() {
+        return String::new();
+    }
+    let eighths = dur.num_seconds() * BAR_WIDTH * 8 / scale.num_seconds();
+    let mut bar = "█".repeat((eighths / 8) as usize);
+    bar += EIGHTHS[(eighths % 8) as usize];
+    bar
+}
+
+#[test]
+fn bars_scale_to_daily_hours() {
+    let daily = Duration::hours(8);
+    assert_eq!(bar(daily, daily), "█".repeat(16));
+    assert_eq!(bar(Duration::hours(1), daily), "██");
+    assert_eq!(bar(Duration::minutes(75), daily), "██▌");
+    assert_eq!(bar(Duration::hours(10), daily).chars().count(), 20);
+    assert_eq!(bar(Duration::zero(), daily), "");
+    assert_eq!(bar(daily, Duration::zero()), "");
+}
@@ -0,0 +1,178 @@
+//! Handler for the export subcommand.
+//!
+//! Writes the entries of one month or a range of days to stdout in a format
+//! suited for reports or spreadsheets.
+
+use crate::balance::{format_span, TimeBalance, WorkEntry};
+use crate::errors::*;
+use crate::month;
+
+use chrono::{DateTime, Datelike, Duration, Local, Month, NaiveDate, Utc};
+use num_traits::FromPrimitive;
+
+use std::io::Write;
+use std::path::Path;
+
+/// Formats the `export` subcommand can write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// GitHub flavored Markdown table.
+    #[value(name = "md")]
+    Markdown,
+    /// Rows of `date,start,stop,duration,note` with a header.
+    Csv,
+}
+
+/// Header of the csv export.
+const CSV_HEADER: &str = "date,start,stop,duration,note";
+
+/// Export entries in `format` to stdout.
+///
+/// With `since` or `until` the entries of that range of days are exported, an
+/// open end reaching to the first or last entry. Otherwise Markdown exports
+/// `month` in the year of `now`, the month of `now` if not set, and csv
+/// exports `month` if set or all entries.
+pub fn export<P: AsRef<Path>>(
+    storage: P,
+    format: ExportFormat,
+    month: Option<month::Month>,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let balance = TimeBalance::from_file_or_legacy(&storage, false)?;
+    if let Some((s, u)) = since.zip(until).filter(|(s, u)| s > u) {
+        bail!(usage_err!(
+            "The start {} of the range is after its end {}",
+            s,
+            u
+        ));
+    }
+    let entries =
+        if since.is_some() || until.is_some() || (month.is_none() && format == ExportFormat::Csv) {
+            match (since.or(balance.first_day()), until.or(balance.last_day())) {
+                (Some(s), Some(u)) => entries_between(&balance, s, u)?,
+                _ => Vec::new(),
+            }
+        } else {
+            let year = now.with_timezone(&Local).year();
+            entries(&balance, year, resolve_month(month, now)?)?
+        };
+
+    let mut out = std::io::stdout().lock();
+    match format {
+        ExportFormat::Markdown => markdown(&balance, &entries, &mut out),
+        ExportFormat::Csv => csv(&entries, &mut out),
+    }
+}
+
+/// Convert `month` to a chrono month, the local month of `now` if not set.
+pub(super) fn resolve_month(month: Option<month::Month>, now: DateTime<Utc>) -> Result<Month> {
+    match month {
+        Some(m) => Month::from_u8(m as u8),
+        None => Month::from_u32(now.with_timezone(&Local).month()),
+    }
+    .ok_or_else(|| eyre!("Failed to parse month"))
+}
+
+/// One exported work entry.
+pub(super) struct ExportEntry {
+    pub start: DateTime<Local>,
+    pub stop: DateTime<Local>,
+    pub duration: Duration,
+    pub note: Option<String>,
+}
+
+impl From<(&DateTime<Utc>, &WorkEntry)> for ExportEntry {
+    fn from((stop, entry): (&DateTime<Utc>, &WorkEntry)) -> Self {
+        Self {
+            start: entry.start(*stop).with_timezone(&Local),
+            stop: stop.with_timezone(&Local),
+            duration: Duration::from(entry),
+            note: entry.note.clone(),
+        }
+    }
+}
+
+/// Start, stop, duration and note of the entries of one `month`.
+pub(super) fn entries(balance: &TimeBalance, year: i32, month: Month) -> Result<Vec<ExportEntry>> {
+    Ok(balance
+        .month_range(year, month)?
+        .map(ExportEntry::from)
+        .collect())
+}
+
+/// Start, stop, duration and note of the entries from the day `since` to the
+/// day `until`, both inclusive.
+fn entries_between(
+    balance: &TimeBalance,
+    since: NaiveDate,
+    until: NaiveDate,
+) -> Result<Vec<ExportEntry>> {
+    Ok(balance
+        .dates_range(since, until, Local)?
+        .map(ExportEntry::from)
+        .collect())
+}
+
+/// Write `entries` as Markdown table with a totals row.
+fn markdown<W: Write>(balance: &TimeBalance, entries: &[ExportEntry], out: &mut W) -> Result<()> {
+    writeln!(out, "| Date | Weekday | Time | Duration | Note |")?;
+    writeln!(out, "|------|---------|------|---------:|------|")?;
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let mut total = Duration::zero();
+    for e in entries {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} | {} |",
+            e.start.format("%Y-%m-%d"),
+            cfg.format_date(e.start.date_naive(), "%A"),
+            format_span(e.start, e.stop),
+            cfg.format_duration(e.duration),
+            e.note.as_deref().unwrap_or_default().replace('|', "\\|")
+        )?;
+        total += e.duration;
+    }
+    writeln!(
+        out,
+        "| **Total** | | | **{}** | |",
+        cfg.format_duration(total)
+    )?;
+    Ok(())
+}
+
+/// Write `entries` as csv with a header row, durations as `H:MM` like the
+/// csv import reads them.
+fn csv<W: Write>(entries: &[ExportEntry], out: &mut W) -> Result<()> {
+    writeln!(out, "{}", CSV_HEADER)?;
+    for e in entries {
+        writeln!(
+            out,
+            "{},{},{},{}:{:02},{}",
+            e.start.format("%Y-%m-%d"),
+            e.start.format("%H:%M"),
+            e.stop.format("%H:%M"),
+            e.duration.num_hours(),
+            e.duration.num_minutes() % 60,
+            csv_field(e.note.as_deref().unwrap_or_default())
+        )?;
+    }
+    Ok(())
+}
+
+/// Quote `field` if it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[test]
+fn quote_csv_fields() {
+    assert_eq!(csv_field(""), "");
+    assert_eq!(csv_field("release"), "release");
+    assert_eq!(csv_field("review, deploy"), "\"review, deploy\"");
+    assert_eq!(csv_field("the \"fix\""), "\"the \"\"fix\"\"\"");
+}
@@ -0,0 +1,141 @@
+//! Handler for the import subcommand.
+//!
+//! Reads work from other tools, e.g. to onboard existing data.
+
+use crate::balance::{DurationDef, TimeBalance, WorkEntry};
+use crate::commands::control::save;
+use crate::delta::parse_duration;
+use crate::errors::*;
+
+use chrono::{Duration, Local, NaiveDate, NaiveTime, Utc};
+
+use std::path::Path;
+
+/// Formats the `import` subcommand can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// Rows of `date,duration` with a header, e.g. `2022-01-12,8:30`.
+    Csv,
+}
+
+/// Header the csv input has to start with.
+const CSV_HEADER: &str = "date,duration";
+
+/// Imported work is assumed to start at this local time of its day.
+const IMPORT_START_HOUR: u32 = 8;
+
+/// Rows read from the input and the skipped lines with the reason.
+struct Batch {
+    rows: Vec<(NaiveDate, Duration)>,
+    skipped: Vec<(usize, &'static str)>,
+}
+
+/// Import the work in `input` in `format` and merge it with the entries of the
+/// same day.
+///
+/// Nothing is written if any row of the input fails to parse.
+pub fn import<P: AsRef<Path>>(
+    storage: P,
+    format: ImportFormat,
+    input: &Path,
+    dry_run: bool,
+) -> Result<()> {
+    let mut balance = TimeBalance::from_file(&storage, false)?;
+    let content = std::fs::read_to_string(input)
+        .wrap_err_with(|| format!("Failed to read '{}'", input.display()))?;
+    let batch = match format {
+        ImportFormat::Csv => csv(&content)?,
+    };
+
+    let start = NaiveTime::from_hms_opt(IMPORT_START_HOUR, 0, 0)
+        .ok_or_else(|| eyre!("Invalid start of imported work"))?;
+    for (day, duration) in &batch.rows {
+        let stop = day
+            .and_time(start)
+            .and_local_timezone(Local)
+            .earliest()
+            .ok_or_else(|| usage_err!("{} has no start in your time zone", day))?
+            .with_timezone(&Utc)
+            + *duration;
+        balance.insert(stop, WorkEntry::from(DurationDef::from(*duration)));
+    }
+    balance.canocicalize()?;
+
+    for (line, reason) in &batch.skipped {
+        println!("Skipped line {}: {}.", line, reason);
+    }
+    println!(
+        "Imported {} rows, skipped {}.",
+        batch.rows.len(),
+        batch.skipped.len()
+    );
+    save(&balance, storage, dry_run)
+}
+
+/// Parse `date,duration` rows after the header, durations are given like
+/// `8:30` or `8h30m`.
+fn csv(content: &str) -> Result<Batch> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .skip_while(|(_, l)| l.is_empty());
+    match lines.next() {
+        Some((_, header)) if header.replace(' ', "").eq_ignore_ascii_case(CSV_HEADER) => {}
+        Some((_, header)) => bail!(usage_err!(
+            "Expected the header '{}' but found '{}'",
+            CSV_HEADER,
+            header
+        )),
+        None => bail!(usage_err!("The input is empty")),
+    }
+
+    let mut batch = Batch {
+        rows: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for (i, line) in lines {
+        if line.is_empty() {
+            batch.skipped.push((i, "blank line"));
+            continue;
+        }
+        let (date, duration) = line
+            .split_once(',')
+            .ok_or_else(|| usage_err!("Line {} is not 'date,duration': '{}'", i, line))?;
+        let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map_err(|e| usage_err!("Line {} has an invalid date '{}': {}", i, date, e))?;
+        let duration = parse_duration(duration.trim())
+            .map_err(|e| usage_err!("Line {} has an invalid duration: {}", i, e))?;
+        if duration.is_zero() {
+            batch.skipped.push((i, "zero duration"));
+            continue;
+        }
+        batch.rows.push((date, duration));
+    }
+    Ok(batch)
+}
+
+#[test]
+fn parse_csv() {
+    let batch = csv("date, duration\n2022-01-12,8:30\n\n2022-01-13,7h\n2022-01-14,0:00\n")
+        .expect("parsing works");
+    assert_eq!(
+        batch.rows,
+        vec![
+            (
+                NaiveDate::from_ymd_opt(2022, 1, 12).unwrap(),
+                Duration::minutes(510)
+            ),
+            (
+                NaiveDate::from_ymd_opt(2022, 1, 13).unwrap(),
+                Duration::hours(7)
+            ),
+        ]
+    );
+    assert_eq!(batch.skipped, vec![(3, "blank line"), (5, "zero duration")]);
+
+    assert!(csv("day,hours\n2022-01-12,8:30").is_err());
+    assert!(csv("date,duration\n2022-01-12,8:30\n12.01.2022,8:30").is_err());
+    assert!(csv("date,duration\n2022-01-12").is_err());
+    assert!(csv("").is_err());
+}
@@ -2,9 +2,16 @@
 //!
 //! Takes care of most of actual application logic, throws errors and writes to
 //! the disk. It is split into `control` module for starting, stopping and
-//! handling periods and a module `stats` for printing statistics about past and
-//! current work periods.
+//! handling periods, a module `stats` for printing statistics about past and
+//! current work periods, a module `export` for writing them in report
+//! formats, a module `import` for reading them from other tools, a module
+//! `report` for rendering them as document and a module `digest` for
+//! summarizing a week.
 
 pub mod config;
 pub mod control;
+pub mod digest;
+pub mod export;
+pub mod import;
+pub mod report;
 pub mod stats;
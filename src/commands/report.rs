@@ -0,0 +1,130 @@
+//! Handler for the report subcommand.
+//!
+//! Renders the entries of one month as self-contained document, e.g. to attach
+//! it to an invoice.
+
+use crate::balance::{format_span, TimeBalance};
+use crate::commands::export::{entries, resolve_month};
+use crate::errors::*;
+use crate::month;
+
+use chrono::{DateTime, Datelike, Duration, Local, Utc};
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Formats the `report` subcommand can render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Self-contained HTML page.
+    Html,
+}
+
+/// Page of the HTML report, `{title}`, `{name}`, `{rows}` and `{totals}` are
+/// replaced by the content.
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; }
+td.num { text-align: right; }
+tfoot td { font-weight: bold; }
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<p>{name}</p>
+<table>
+<thead><tr><th>Date</th><th>Weekday</th><th>Time</th><th>Duration</th><th>Note</th></tr></thead>
+<tbody>
+{rows}</tbody>
+<tfoot>
+{totals}</tfoot>
+</table>
+</body>
+</html>
+"#;
+
+/// Render the entries of `month` in the year of `now`, the month of `now` if
+/// not set, in `format` to `output` or stdout.
+pub fn report<P: AsRef<Path>>(
+    storage: P,
+    format: ReportFormat,
+    month: Option<month::Month>,
+    output: Option<PathBuf>,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let balance = TimeBalance::from_file_or_legacy(&storage, false)?;
+    let year = now.with_timezone(&Local).year();
+    let month = resolve_month(month, now)?;
+
+    let rendered = match format {
+        ReportFormat::Html => html(&balance, year, month)?,
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(&path, rendered)
+                .wrap_err_with(|| format!("Failed to write report '{}'", path.display()))?;
+            println!("Wrote report to '{}'.", path.display());
+        }
+        None => std::io::stdout().write_all(rendered.as_bytes())?,
+    }
+    Ok(())
+}
+
+/// Render the entries of one `month` as HTML page with totals and earnings.
+fn html(balance: &TimeBalance, year: i32, month: chrono::Month) -> Result<String> {
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let mut rows = String::new();
+    let mut total = Duration::zero();
+    for e in entries(balance, year, month)? {
+        rows += &format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td>{}</td></tr>\n",
+            e.start.format("%Y-%m-%d"),
+            cfg.format_date(e.start.date_naive(), "%A"),
+            format_span(e.start, e.stop),
+            cfg.format_duration(e.duration),
+            escape(e.note.as_deref().unwrap_or_default())
+        );
+        total += e.duration;
+    }
+    let mut totals = format!(
+        "<tr><td colspan=\"3\">Total</td><td class=\"num\">{}</td><td></td></tr>\n",
+        cfg.format_duration(total)
+    );
+    if let Some(e) = cfg.earnings(total) {
+        totals += &format!(
+            "<tr><td colspan=\"3\">Earnings</td><td class=\"num\">{}</td><td></td></tr>\n",
+            escape(&e)
+        );
+    }
+
+    Ok(HTML_TEMPLATE
+        .replace(
+            "{title}",
+            &format!("Work report {} {}", cfg.month_name(month), year),
+        )
+        .replace(
+            "{name}",
+            &escape(balance.name.as_deref().unwrap_or_default()),
+        )
+        .replace("{rows}", &rows)
+        .replace("{totals}", &totals))
+}
+
+/// Escape characters with a special meaning in HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[test]
+fn escape_html() {
+    assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+}
@@ -2,46 +2,557 @@
 //!
 //! The main entry point is `stats` which then further decides what to do.
 
-use crate::balance::{Config, DurationDef, TimeBalance};
+use crate::balance::{
+    entry_day, format_span, month_bounds, Config, DayKind, TimeBalance, WorkEntry,
+};
 
+use crate::cli_input::YesNo;
 use crate::errors::*;
 use crate::month;
-use chrono::{DateTime, Datelike, Duration, Local, Month, Utc};
+use chrono::{DateTime, Datelike, Duration, Local, Month, NaiveDate, Utc};
 use colored::*;
 use itertools::Itertools;
 use num_traits::FromPrimitive;
+use serde::Serialize;
 
 use std::path::Path;
 
+/// Options of the `stats` subcommand.
+#[derive(Debug, Default)]
+pub struct StatsOptions {
+    /// Month of which the stats are shown, the last months if not set.
+    pub month: Option<month::Month>,
+    /// Print json instead of text.
+    pub json: bool,
+    /// Only print the total of all tracked work.
+    pub total: bool,
+    /// Print the total of each day in the monthly stats.
+    pub daily: bool,
+    /// Only print the entries of the current week.
+    pub week: bool,
+    /// First day of a range to print instead of months.
+    pub since: Option<NaiveDate>,
+    /// Last day of the range, defaults to today.
+    pub until: Option<NaiveDate>,
+    /// Number of past months to print instead of the configured one.
+    pub months: Option<u8>,
+    /// Group by pay periods instead of calendar months.
+    pub periods: bool,
+    /// Only count work with one of these tags.
+    pub tags: Vec<String>,
+    /// Count untagged work.
+    pub untagged: bool,
+}
+
+/// Machine readable summary of the statistics, printed by `stats --json`.
+///
+/// All durations are given in seconds.
+#[derive(Debug, Serialize)]
+struct StatsReport {
+    months: Vec<MonthReport>,
+    state: StateReport,
+    total_secs: i64,
+    worked_days: usize,
+}
+
+/// Total of one month and its weeks.
+#[derive(Debug, Serialize)]
+struct MonthReport {
+    year: i32,
+    month: u32,
+    total_secs: i64,
+    weeks: Vec<WeekReport>,
+}
+
+/// Total of one calendar week.
+#[derive(Debug, Serialize)]
+struct WeekReport {
+    week: u32,
+    total_secs: i64,
+}
+
+/// Currently running work and breaks.
+#[derive(Debug, Serialize)]
+struct StateReport {
+    started: Option<DateTime<Utc>>,
+    worked_secs: i64,
+    on_break_since: Option<DateTime<Utc>>,
+    break_secs: i64,
+    overhours_secs: Option<i64>,
+}
+
 /// Prints a summary of the current storage either for one month.
 ///
-/// Handler for the `stats` sub command.
-pub fn stats<P: AsRef<Path>>(storage: P, month: Option<month::Month>) -> Result<()> {
-    let year = Utc::now().year();
-    let balance = TimeBalance::from_file(&storage, false)?;
-    if let Some(m) = month {
+/// Handler for the `stats` sub command, see `StatsOptions` for what is printed.
+pub fn stats<P: AsRef<Path>>(
+    storage: P,
+    opts: StatsOptions,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<()> {
+    let year = now.year();
+    // A missing storage is read as empty balance, stats only write it to stop
+    // forgotten work.
+    let mut balance = TimeBalance::from_file_or_legacy(&storage, true)?;
+    if !opts.json {
+        offer_auto_stop(&storage, &mut balance, now, dry_run)?;
+    }
+    let empty = balance.first_day().is_none() && balance.start_state(now).is_none();
+    if empty && !opts.json {
+        if storage.as_ref().exists() {
+            println!("No data yet, run 'stempel start' to track your work.");
+        } else {
+            println!("No data yet, run 'stempel init' and 'stempel start' to track your work.");
+        }
+        return Ok(());
+    }
+    let filtered = !opts.tags.is_empty() || opts.untagged;
+    if filtered {
+        balance.retain_tagged(&opts.tags, opts.untagged);
+        // Filtered work is only part of a day, it can't meet the daily hours.
+        if let Some(cfg) = balance.config.as_mut() {
+            cfg.daily_hours = None;
+            cfg.weekly_hours = None;
+        }
+    }
+    let month = opts.month;
+    let months = if let Some(m) = month {
         let m = Month::from_u8(m as u8).ok_or_else(|| eyre!("Failed to parse {} into month", m))?;
-        monthly_stats(&balance, year, m)?;
+        vec![(year, m)]
     } else {
-        let m = Month::from_u32(Utc::now().month())
-            .ok_or_else(|| eyre!("Failed to parse current month"))?;
+        let m =
+            Month::from_u32(now.month()).ok_or_else(|| eyre!("Failed to parse current month"))?;
         let default_cfg = Config::default();
-        let history = balance.config.as_ref().unwrap_or(&default_cfg).month_stats;
+        let history = match opts.months {
+            Some(n) => {
+                let available = available_months(&balance, year, m);
+                if n > available {
+                    println!("Your tracked work only goes back {} months.", available);
+                }
+                n.min(available)
+            }
+            None => balance.config.as_ref().unwrap_or(&default_cfg).month_stats,
+        };
         if history > 0 {
-            println!("Here are your stats for the last {} months:", history);
-            stats_last_month(&balance, year, m, history)?;
+            last_months(year, m, history)
+        } else {
+            Vec::new()
+        }
+    };
+
+    if opts.json {
+        return json_stats(&balance, &months, now);
+    }
+    if opts.week {
+        current_week(&balance, now.with_timezone(&Local).date_naive());
+        return Ok(());
+    }
+    if let Some(since) = opts.since {
+        let until = opts
+            .until
+            .unwrap_or_else(|| now.with_timezone(&Local).date_naive());
+        return range_stats(&balance, since, until);
+    }
+    if opts.total {
+        let cfg = balance.config.as_ref().unwrap_or_default();
+        println!(
+            "You tracked {} on {} days in total.",
+            cfg.format_duration(balance.total_worked()),
+            balance.worked_days()
+        );
+        if let Some(e) = cfg.earnings(balance.total_worked()) {
+            println!("That's {} of earnings.", e);
         }
-        weekly_stats(&balance)?;
+        return Ok(());
+    }
+
+    let periods = if opts.periods {
+        let cfg = balance.config.as_ref().unwrap_or_default();
+        Some(pay_periods(
+            cfg,
+            now.with_timezone(&Local).date_naive(),
+            months.len(),
+        )?)
+    } else {
+        None
+    };
+
+    if let Some(name) = &balance.name {
+        println!("Hi {}!", name.green());
+    }
+    if month.is_none() && !months.is_empty() {
+        println!(
+            "Here are your stats for the last {} {}:",
+            months.len() - 1,
+            if periods.is_some() {
+                "pay periods"
+            } else {
+                "months"
+            }
+        );
+    }
+    let mut total = Duration::zero();
+    match periods {
+        Some(periods) => {
+            for (since, until) in periods {
+                total += period_stats(&balance, since, until, opts.daily)?;
+            }
+        }
+        None => {
+            for (y, m) in months {
+                total += monthly_stats(&balance, y, m, opts.daily)?;
+            }
+        }
+    }
+    if let Some(e) = balance.config.as_ref().unwrap_or_default().earnings(total) {
+        println!("{}", row(0, "Total earnings:", &e).bold());
+    }
+    if month.is_none() {
+        weekly_stats(&balance, now.with_timezone(&Local).date_naive())?;
+    }
+    if filtered {
+        return Ok(());
     }
 
     println!();
-    show_state(&balance);
+    show_state(&balance, now);
+    if let Some(((short_day, short), (long_day, long))) = balance.extremes() {
+        let cfg = balance.config.as_ref().unwrap_or_default();
+        println!(
+            "Your shortest day was {} with {}, your longest {} with {}.",
+            short_day.format("%d.%m.%Y"),
+            cfg.format_duration(short),
+            long_day.format("%d.%m.%Y"),
+            cfg.format_duration(long)
+        );
+    }
+    if let Some(avg) = balance.avg_start_time(now, Local) {
+        match balance.config.as_ref().unwrap_or_default().avg_window() {
+            Some(days) => println!(
+                "Average start time (last {} days): {}",
+                days,
+                avg.format("%H:%M")
+            ),
+            None => println!("Average start time: {}", avg.format("%H:%M")),
+        }
+    }
+    if let Some(b) = balance.break_stats() {
+        let cfg = balance.config.as_ref().unwrap_or_default();
+        println!("\nBreaks:");
+        println!("{}", row(4, "Taken:", &b.count.to_string()));
+        println!("{}", row(4, "Total:", &cfg.format_duration(b.total)));
+        println!("{}", row(4, "Average:", &cfg.format_duration(b.average)));
+    }
+
+    Ok(())
+}
+
+/// Offers to stop work still running past the configured `auto_stop_at` at
+/// that time instead of `now` and saves the balance if accepted.
+fn offer_auto_stop<P: AsRef<Path>>(
+    storage: P,
+    balance: &mut TimeBalance,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<()> {
+    let Some(stop) = balance.overdue_auto_stop(now) else {
+        return Ok(());
+    };
+    println!(
+        "You're still working, stop at {} as configured? [y/N]",
+        stop.with_timezone(&Local).format("%H:%M")
+    );
+    if let YesNo::No = YesNo::wait_for_decision()? {
+        return Ok(());
+    }
+    if balance.break_state(now).current.is_some_and(|b| b < stop) {
+        balance.finish_break(stop)?;
+    }
+    let worked = balance.stop(stop, None)?;
+    balance.canocicalize()?;
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    println!("Stopped, you worked {}.", cfg.format_duration(worked));
+    super::control::save(balance, storage, dry_run)
+}
+
+/// Prints the net time worked today including the running work and the time
+/// remaining to the daily working hours, as of `now`.
+///
+/// Handler for the `today` sub command.
+pub fn today<P: AsRef<Path>>(storage: P, now: DateTime<Utc>, dry_run: bool) -> Result<()> {
+    let mut balance = TimeBalance::from_file_or_legacy(&storage, false)?;
+    offer_auto_stop(&storage, &mut balance, now, dry_run)?;
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let today = now.with_timezone(&Local).date_naive();
+    let worked = worked_today(&balance, now)?;
+    let break_state = balance.break_state(now);
+
+    println!("Today you worked {}.", cfg.format_duration(worked).green());
+    if let Some(start) = break_state.current {
+        println!(
+            "You're on a break since {}.",
+            start.with_timezone(&Local).format("%H:%M")
+        );
+    }
+    if balance.is_day_off(today) {
+        println!("Today is a day off, every minute counts as overtime.");
+    } else if let Some(daily) = cfg.daily_hours {
+        let daily = Duration::hours(daily as i64);
+        let (bar, percent) = progress_bar(worked, daily);
+        match percent {
+            p if p < 50 => println!("{}", bar.red()),
+            p if p >= 100 => println!("{}", bar.green()),
+            _ => println!("{}", bar),
+        }
+        let remaining = daily - worked;
+        if remaining > Duration::zero() {
+            println!("You still need to work {}.", cfg.format_duration(remaining));
+        } else {
+            println!(
+                "You're done for today with {} overhours.",
+                cfg.format_duration(-remaining)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Prints a terse summary of yesterday and of today so far as of `now`,
+/// e.g. to paste it into a standup.
+///
+/// Handler for the `standup` sub command.
+pub fn standup<P: AsRef<Path>>(storage: P, now: DateTime<Utc>) -> Result<()> {
+    let balance = TimeBalance::from_file_or_legacy(&storage, false)?;
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let today = now.with_timezone(&Local).date_naive();
+    let yesterday = today
+        .pred_opt()
+        .ok_or_else(|| eyre!("Failed to find the day before {}", today))?;
+    let worked_on = |day| -> Result<(Duration, Duration)> {
+        Ok(balance
+            .daily_range(day, Local)?
+            .fold((Duration::zero(), Duration::zero()), |(w, b), (_, e)| {
+                (w + Duration::from(e), b + e.break_sum())
+            }))
+    };
+
+    let (worked, breaks) = worked_on(yesterday)?;
+    let kind = match balance.day_marker(yesterday) {
+        Some(kind) => format!(", {}", kind),
+        None if yesterday.weekday().num_days_from_monday() >= 5 => ", weekend".to_string(),
+        None => String::new(),
+    };
+    let summary = if worked.is_zero() {
+        "nothing tracked".to_string()
+    } else {
+        format!(
+            "{} worked, {} breaks",
+            cfg.format_duration(worked),
+            cfg.format_duration(breaks)
+        )
+    };
+    println!(
+        "Yesterday ({}{}): {}",
+        cfg.format_date(yesterday, "%a %d.%m."),
+        kind,
+        summary
+    );
+
+    let (_, finished_breaks) = worked_on(today)?;
+    let break_state = balance.break_state(now);
+    let running_breaks = match balance.start_state(now) {
+        Some(_) => break_state.sum,
+        None => Duration::zero(),
+    };
+    let mut line = format!(
+        "Today ({}): {} worked, {} breaks",
+        cfg.format_date(today, "%a %d.%m."),
+        cfg.format_duration(worked_today(&balance, now)?),
+        cfg.format_duration(finished_breaks + running_breaks)
+    );
+    match (balance.start_state(now), break_state.current) {
+        (_, Some(b)) => {
+            line += &format!(
+                ", on a break since {}",
+                b.with_timezone(&Local).format("%H:%M")
+            )
+        }
+        (Some((_, s)), None) => {
+            line += &format!(
+                ", working since {}",
+                s.with_timezone(&Local).format("%H:%M")
+            )
+        }
+        (None, None) => {}
+    }
+    println!("{}", line);
+    Ok(())
+}
+
+/// Plain text bar of `worked` toward the `daily` hours like
+/// `[########------------] 40%` and the percentage reached.
+fn progress_bar(worked: Duration, daily: Duration) -> (String, i64) {
+    let percent = if daily > Duration::zero() {
+        worked.num_seconds().max(0) * 100 / daily.num_seconds()
+    } else {
+        100
+    };
+    let filled = (percent.min(100) as usize * PROGRESS_WIDTH) / 100;
+    let bar = format!(
+        "[{}{}] {}%",
+        "#".repeat(filled),
+        "-".repeat(PROGRESS_WIDTH - filled),
+        percent
+    );
+    (bar, percent)
+}
+
+/// Net time worked on the day of `now`, including the running work without
+/// its breaks.
+fn worked_today(balance: &TimeBalance, now: DateTime<Utc>) -> Result<Duration> {
+    let finished = balance
+        .daily_range(now.with_timezone(&Local).date_naive(), Local)?
+        .fold(Duration::zero(), |acc, (_, e)| acc + Duration::from(e));
+    let running = balance
+        .start_state(now)
+        .map(|(dur, _)| dur - balance.break_state(now).sum)
+        .unwrap_or_else(Duration::zero);
+    Ok(finished + running)
+}
+
+/// Warn if the work in the `storage` started so long before `now` that the
+/// stop was probably forgotten. A missing or unreadable storage is ignored,
+/// the command itself reports it.
+pub fn warn_if_idle<P: AsRef<Path>>(storage: P, now: DateTime<Utc>) {
+    if let Some(dur) = TimeBalance::from_file(&storage, false)
+        .ok()
+        .and_then(|b| b.idle(now))
+    {
+        log::warn!(
+            "You started {}h ago, did you forget to stop? Run 'stempel stop' or 'stempel cancel'.",
+            dur.num_hours()
+        );
+    }
+}
+
+/// Check whether the daily working hours are exceeded today and send a
+/// desktop notification if enabled, e.g. from a timer. Today is the day of
+/// `now`.
+///
+/// Handler for the `check` sub command.
+pub fn check<P: AsRef<Path>>(storage: P, now: DateTime<Utc>) -> Result<()> {
+    let balance = TimeBalance::from_file_or_legacy(&storage, false)?;
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let today = now.with_timezone(&Local).date_naive();
+    let Some(daily) = cfg.daily_hours.filter(|_| !balance.is_day_off(today)) else {
+        return Ok(());
+    };
+    let over = worked_today(&balance, now)? - Duration::hours(daily as i64);
+    if over <= Duration::zero() {
+        return Ok(());
+    }
+    let msg = format!(
+        "You exceeded your daily {}h by {}, time to stop!",
+        daily,
+        cfg.format_duration(over)
+    );
+    println!("{}", msg);
+    if cfg.notifications.unwrap_or_default() {
+        notify(&msg)?;
+    }
+    Ok(())
+}
+
+/// Show `body` as desktop notification.
+#[cfg(feature = "notify")]
+fn notify(body: &str) -> Result<()> {
+    notify_rust::Notification::new()
+        .summary("stempel")
+        .body(body)
+        .show()
+        .wrap_err("Failed to show the notification")?;
+    Ok(())
+}
+
+/// Show `body` as desktop notification.
+#[cfg(not(feature = "notify"))]
+fn notify(_body: &str) -> Result<()> {
+    bail!(feature_disabled("notify"))
+}
+
+/// Prints all breaks taken on one `day`.
+///
+/// Handler for the `breaks` sub command.
+pub fn breaks<P: AsRef<Path>>(storage: P, day: NaiveDate) -> Result<()> {
+    let balance = TimeBalance::from_file_or_legacy(&storage, false)?;
+    let breaks = balance.breaks_on(day, Local)?;
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    if breaks.is_empty() {
+        println!("No breaks on {}.", day.format("%d.%m.%Y"));
+        return Ok(());
+    }
 
+    println!(
+        "Breaks on {}:",
+        cfg.format_date(day, "%A, %d.%m.%Y").green()
+    );
+    let mut sum = Duration::zero();
+    for (start, dur) in breaks {
+        sum += dur;
+        println!(
+            "    {} for {}",
+            start.with_timezone(&Local).format("%H:%M"),
+            cfg.format_duration(dur)
+        );
+    }
+    println!("Total: {}", cfg.format_duration(sum));
     Ok(())
 }
 
-/// Generate month, year combination for past months and print the respective stats for them.
-fn stats_last_month(balance: &TimeBalance, year: i32, month: Month, history: u8) -> Result<()> {
+/// Prints the running break and all finished breaks of the day of `now`.
+///
+/// Handler for the `break list` sub command.
+pub fn break_list<P: AsRef<Path>>(storage: P, now: DateTime<Utc>) -> Result<()> {
+    let balance = TimeBalance::from_file_or_legacy(&storage, false)?;
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    match balance.break_state(now).current {
+        Some(s) => println!(
+            "On a break since {}, {} so far.",
+            s.with_timezone(&Local).format("%H:%M").to_string().green(),
+            cfg.format_duration(now.signed_duration_since(s))
+        ),
+        None => println!("Not on a break."),
+    }
+    let breaks = balance.breaks_on(now.with_timezone(&Local).date_naive(), Local)?;
+    if breaks.is_empty() {
+        println!("No finished breaks today.");
+        return Ok(());
+    }
+    println!("Finished breaks today:");
+    for (start, dur) in breaks {
+        println!(
+            "    {} for {}",
+            start.with_timezone(&Local).format("%H:%M"),
+            cfg.format_duration(dur)
+        );
+    }
+    Ok(())
+}
+
+/// Number of months before `month` of `year` back to the first tracked work.
+fn available_months(balance: &TimeBalance, year: i32, month: Month) -> u8 {
+    match balance.first_day() {
+        Some(first) => {
+            let now = year * 12 + month.number_from_month() as i32;
+            let first = first.year() * 12 + first.month() as i32;
+            (now - first).clamp(0, u8::MAX.into()) as u8
+        }
+        None => 0,
+    }
+}
+
+/// Generate month, year combinations for the past `history` months up to `month`.
+fn last_months(year: i32, month: Month, history: u8) -> Vec<(i32, Month)> {
     let mut months: Vec<Month> = vec![month];
     let mut years: Vec<i32> = vec![year];
     (0..history).fold(month, |a, _| {
@@ -57,14 +568,72 @@ fn stats_last_month(balance: &TimeBalance, year: i32, month: Month, history: u8)
     months.reverse();
     log::trace!("Years: {:?}, months: {:?}", years, months);
 
-    for (y, m) in years.iter().zip(months) {
-        monthly_stats(balance, *y, m)?;
-    }
+    years.into_iter().zip(months).collect()
+}
+
+/// The last `count` pay periods up to the one of `today`, oldest first.
+fn pay_periods(
+    cfg: &Config,
+    today: NaiveDate,
+    count: usize,
+) -> Result<Vec<(NaiveDate, NaiveDate)>> {
+    let current = cfg.pay_period(today).ok_or_else(|| {
+        usage_err!("No pay periods configured, run 'stempel configure --period-start-day <DAY>'")
+    })?;
+    let mut periods: Vec<_> = std::iter::successors(Some(current), |(since, _)| {
+        since.pred_opt().and_then(|d| cfg.pay_period(d))
+    })
+    .take(count)
+    .collect();
+    periods.reverse();
+    Ok(periods)
+}
+
+/// Serialize the totals of `months` and the state at `now` to json and print it.
+fn json_stats(balance: &TimeBalance, months: &[(i32, Month)], now: DateTime<Utc>) -> Result<()> {
+    let months = months
+        .iter()
+        .map(|(y, m)| {
+            let weeks: Vec<WeekReport> = week_totals(balance, *y, *m)?
+                .into_iter()
+                .map(|(week, dur)| WeekReport {
+                    week,
+                    total_secs: dur.num_seconds(),
+                })
+                .collect();
+            Ok(MonthReport {
+                year: *y,
+                month: m.number_from_month(),
+                total_secs: weeks.iter().map(|w| w.total_secs).sum(),
+                weeks,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let break_state = balance.break_state(now);
+    let (started, worked) = match balance.start_state(now) {
+        Some((dur, start)) => (Some(start), dur - break_state.sum),
+        None => (None, Duration::zero()),
+    };
+    let report = StatsReport {
+        months,
+        state: StateReport {
+            started,
+            worked_secs: worked.num_seconds(),
+            on_break_since: break_state.current,
+            break_secs: break_state.sum.num_seconds(),
+            overhours_secs: balance.calculate_overhours().map(|d| d.num_seconds()),
+        },
+        total_secs: balance.total_worked().num_seconds(),
+        worked_days: balance.worked_days(),
+    };
+    let json = serde_json::to_string_pretty(&report).wrap_err("Failed to serialize stats")?;
+    println!("{}", json);
     Ok(())
 }
 
-/// Weekly stats
-fn weekly_stats(balance: &TimeBalance) -> Result<()> {
+/// Weekly stats of the week of `today`.
+fn weekly_stats(balance: &TimeBalance, today: NaiveDate) -> Result<()> {
     if balance
         .config
         .as_ref()
@@ -73,55 +642,377 @@ fn weekly_stats(balance: &TimeBalance) -> Result<()> {
         .unwrap_or_default()
     {
         println!("\n");
-        let week_entries: Vec<(_, _)> = balance.week_entries(Local::now().date_naive()).collect();
-        let mut sum = DurationDef::zero();
-        for (start, dur) in week_entries {
-            sum += *dur;
-            println!("{:9} {}", start.format("%A"), dur);
-        }
-        println!("----------------");
-        println!("Total     {}", sum);
+        current_week(balance, today);
     }
     Ok(())
 }
 
-/// Prints the entries in the `storage` for one `month` grouped by weeks.
-fn monthly_stats(balance: &TimeBalance, year: i32, month: Month) -> Result<()> {
-    let month_entries: Vec<(&DateTime<Utc>, &DurationDef)> =
-        balance.month_range(year, month)?.collect();
+/// Print the entries of the week of `today`, their total and the time
+/// remaining to the configured weekly working hours.
+fn current_week(balance: &TimeBalance, today: NaiveDate) {
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let mut sum = Duration::zero();
+    for (stop, entry) in balance.week_entries(today) {
+        let dur = Duration::from(entry);
+        sum += dur;
+        let label = day_label(cfg, entry_day(stop));
+        print_row(0, &label, &cfg.format_duration(dur), dur, "");
+    }
+    println!("{}", "-".repeat(LABEL_WIDTH + VALUE_WIDTH));
+    println!(
+        "{}",
+        row(0, "Total", &cfg.format_duration(rounded(cfg, sum))).bold()
+    );
+    if let Some(h) = cfg.weekly_hours {
+        let remaining = Duration::hours(h as i64) - sum;
+        if remaining > Duration::zero() {
+            println!("{}", row(0, "Remaining", &cfg.format_duration(remaining)));
+        } else {
+            println!("{}", "You reached your weekly hours 🎉".green());
+        }
+    }
+}
+
+/// Days on which more than this was worked need at least `MIN_BREAK_MINUTES`
+/// of breaks.
+const BREAK_REQUIRED_AFTER_HOURS: i64 = 6;
+
+/// Minimum of breaks on days with more than `BREAK_REQUIRED_AFTER_HOURS` of work.
+const MIN_BREAK_MINUTES: i64 = 30;
+
+/// Width of the label column including its indentation in the stats tables.
+const LABEL_WIDTH: usize = 26;
+
+/// Width of a duration column, fits `HHH:MM:SSh`.
+const VALUE_WIDTH: usize = 10;
+
+/// Number of characters between the brackets of the progress bar in `today`.
+const PROGRESS_WIDTH: usize = 20;
+
+/// Format a table row of the stats with the `label` indented by `indent` and
+/// the `value` right-aligned, so rows of different depth line up.
+fn row(indent: usize, label: &str, value: &str) -> String {
+    format!(
+        "{:indent$}{:<width$}{:>VALUE_WIDTH$}",
+        "",
+        label,
+        value,
+        width = LABEL_WIDTH.saturating_sub(indent)
+    )
+}
+
+/// Label of a day row, fixed-width so the dates line up.
+fn day_label(cfg: &Config, day: NaiveDate) -> String {
+    format!("{:<9} {}", cfg.format_date(day, "%A"), day.format("%d.%m."))
+}
+
+/// Print a total row highlighted, followed by its earnings if configured.
+fn print_total(cfg: &Config, indent: usize, label: &str, total: Duration) {
+    println!("{}", row(indent, label, &cfg.format_duration(total)).bold());
+    if let Some(e) = cfg.earnings(total) {
+        println!("{}", row(indent, "Earnings:", &e));
+    }
+}
+
+/// Print a row dimmed if `dur` is zero.
+fn print_row(indent: usize, label: &str, value: &str, dur: Duration, note: &str) {
+    let line = row(indent, label, value);
+    if dur.is_zero() {
+        println!("{}{}", line.dimmed(), note);
+    } else {
+        println!("{}{}", line, note);
+    }
+}
+
+/// Round `dur` to the nearest quarter hour if configured, only used for
+/// displaying totals.
+fn rounded(cfg: &Config, dur: Duration) -> Duration {
+    if !cfg.display_round_quarter.unwrap_or_default() {
+        return dur;
+    }
+    let quarter = 15 * 60;
+    Duration::seconds((dur.num_seconds() + quarter / 2).div_euclid(quarter) * quarter)
+}
+
+/// Format the work running for `dur`, rounded like the totals so the preview
+/// matches the stats after stopping.
+fn live_worked(cfg: &Config, dur: Duration) -> String {
+    cfg.format_duration(rounded(cfg, dur))
+}
+
+/// Work and breaks of one local day.
+struct DayTotal {
+    day: NaiveDate,
+    /// First start and last stop of the work on this day.
+    span: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Start, stop and work of each entry on this day.
+    sessions: Vec<(DateTime<Utc>, DateTime<Utc>, Duration)>,
+    worked: Duration,
+    breaks: Duration,
+    notes: Vec<String>,
+}
+
+impl DayTotal {
+    /// Whether the breaks on this day are too short for the time worked.
+    fn lacks_break(&self) -> bool {
+        self.worked > Duration::hours(BREAK_REQUIRED_AFTER_HOURS)
+            && self.breaks < Duration::minutes(MIN_BREAK_MINUTES)
+    }
+
+    /// Time from start to stop on this day, i.e. work including breaks.
+    fn gross(&self) -> Duration {
+        self.worked + self.breaks
+    }
+}
+
+/// Sum up the entries of one `month` per local day.
+fn day_totals(balance: &TimeBalance, year: i32, month: Month) -> Result<Vec<DayTotal>> {
     log::trace!("Month {:?}", month);
+    Ok(group_days(balance.month_range(year, month)?))
+}
+
+/// Sum up `entries` per local day.
+fn group_days<'a>(
+    entries: impl Iterator<Item = (&'a DateTime<Utc>, &'a WorkEntry)>,
+) -> Vec<DayTotal> {
+    entries
+        .group_by(|(k, _)| entry_day(k))
+        .into_iter()
+        .map(|(day, group)| {
+            group.fold(
+                DayTotal {
+                    day,
+                    span: None,
+                    sessions: Vec::new(),
+                    worked: Duration::zero(),
+                    breaks: Duration::zero(),
+                    notes: Vec::new(),
+                },
+                |mut total, (stop, e)| {
+                    let start = e.start(*stop);
+                    total.span = Some(match total.span {
+                        Some((s, t)) => (s.min(start), t.max(*stop)),
+                        None => (start, *stop),
+                    });
+                    total.sessions.push((start, *stop, Duration::from(e)));
+                    total.worked += Duration::from(e);
+                    total.breaks += e.break_sum();
+                    total.notes.extend(e.note.clone());
+                    total
+                },
+            )
+        })
+        .collect()
+}
+
+/// Prints the entries from `since` to `until` grouped by weeks and their total.
+fn range_stats(balance: &TimeBalance, since: NaiveDate, until: NaiveDate) -> Result<()> {
+    if since > until {
+        bail!(usage_err!(
+            "The start {} of the range is after its end {}",
+            since,
+            until
+        ));
+    }
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let days = group_days(balance.dates_range(since, until, Local)?);
+    println!(
+        "{}:",
+        format!(
+            "{} to {}",
+            since.format("%d.%m.%Y"),
+            until.format("%d.%m.%Y")
+        )
+        .green()
+    );
+    for (week, group) in &days.iter().group_by(|d| d.day.iso_week()) {
+        let dur = rounded(cfg, group.fold(Duration::zero(), |acc, d| acc + d.worked));
+        let label = format!("Week {:2}:", week.week());
+        print_row(4, &label, &cfg.format_duration(dur), dur, "");
+    }
+    let total = rounded(
+        cfg,
+        days.iter().fold(Duration::zero(), |acc, d| acc + d.worked),
+    );
+    print_total(cfg, 4, "Total:", total);
+    Ok(())
+}
+
+/// Sum up the entries of one `month` grouped by calendar weeks.
+fn week_totals(balance: &TimeBalance, year: i32, month: Month) -> Result<Vec<(u32, Duration)>> {
+    let weeks = day_totals(balance, year, month)?
+        .into_iter()
+        .group_by(|d| d.day.iso_week().week())
+        .into_iter()
+        .map(|(week, group)| (week, group.fold(Duration::zero(), |acc, d| acc + d.worked)))
+        .collect();
+    Ok(weeks)
+}
+
+/// Prints the entries in the `storage` for one `month` grouped by weeks, and
+/// each day if `daily` is set.
+///
+/// Days below the configured daily working hours are highlighted, days with
+/// too short breaks are warned about.
+fn monthly_stats(balance: &TimeBalance, year: i32, month: Month, daily: bool) -> Result<Duration> {
+    let days = day_totals(balance, year, month)?;
+    let (first, last) = month_bounds(year, month)?;
+    let marked = balance.marked_days(first, last);
+    if days.is_empty() && marked.is_empty() {
+        return Ok(Duration::zero());
+    }
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let over = balance.overhours_for_month(year, month)?;
+    Ok(print_days(
+        balance,
+        &cfg.month_name(month),
+        &days,
+        &marked,
+        over,
+        daily,
+    ))
+}
 
-    if !month_entries.is_empty() {
-        println!("{}:", month.name().green());
-        let mut cur_w = 0;
-        for (week, group) in &month_entries.into_iter().group_by(|e| {
-            let week_num = e.0.iso_week().week();
-            if week_num != cur_w {
-                cur_w = week_num;
+/// Prints the entries of the pay period from `since` to `until` like
+/// `monthly_stats`, labeled with its span like `26 May – 25 Jun`.
+fn period_stats(
+    balance: &TimeBalance,
+    since: NaiveDate,
+    until: NaiveDate,
+    daily: bool,
+) -> Result<Duration> {
+    let days = group_days(balance.dates_range(since, until, Local)?);
+    let marked = balance.marked_days(since, until);
+    if days.is_empty() && marked.is_empty() {
+        return Ok(Duration::zero());
+    }
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let label = format!(
+        "{} – {}",
+        cfg.format_date(since, "%-d %b"),
+        cfg.format_date(until, "%-d %b")
+    );
+    let over = balance.overhours_for_range(since, until)?;
+    Ok(print_days(balance, &label, &days, &marked, over, daily))
+}
+
+/// Prints `days` under `label` grouped by weeks with their total, the
+/// `marked` days off and the overtime `over`, see `monthly_stats`.
+fn print_days(
+    balance: &TimeBalance,
+    label: &str,
+    days: &[DayTotal],
+    marked: &[(NaiveDate, DayKind)],
+    over: Option<Duration>,
+    daily: bool,
+) -> Duration {
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let target = cfg.daily_hours.map(|h| Duration::hours(h as i64));
+    let under_target =
+        |d: &DayTotal| target.is_some_and(|t| d.worked < t && !balance.is_day_off(d.day));
+
+    println!("{}:", label.green());
+    if daily {
+        println!("    Days show gross (including breaks) / net working time and their span.");
+    }
+    let mut short_days = 0;
+    for (week, group) in &days.iter().group_by(|d| d.day.iso_week().week()) {
+        let group: Vec<_> = group.collect();
+        let dur = rounded(
+            cfg,
+            group.iter().fold(Duration::zero(), |acc, d| acc + d.worked),
+        );
+        let short = group.iter().filter(|d| under_target(d)).count();
+        short_days += short;
+        let note = match short {
+            0 => String::new(),
+            1 => format!(" {}", "(1 day under target)".red()),
+            n => format!(" {}", format!("({} days under target)", n).red()),
+        };
+        let label = format!("Week {:2}:", week);
+        print_row(4, &label, &cfg.format_duration(dur), dur, &note);
+        if daily {
+            for d in group {
+                let value = format!(
+                    "{:>VALUE_WIDTH$} / {:>VALUE_WIDTH$}",
+                    cfg.format_duration(d.gross()),
+                    cfg.format_duration(d.worked)
+                );
+                let mut line = row(8, &format!("{}:", day_label(cfg, d.day)), &value);
+                if let Some((start, stop)) = d.span {
+                    line += &format!(
+                        "  {}",
+                        format_span(start.with_timezone(&Local), stop.with_timezone(&Local))
+                    );
+                }
+                if under_target(d) {
+                    println!("{} !", line.red());
+                } else if d.worked.is_zero() {
+                    println!("{}", line.dimmed());
+                } else {
+                    println!("{}", line);
+                }
+                if !cfg.merges_same_day() && d.sessions.len() > 1 {
+                    for (start, stop, worked) in &d.sessions {
+                        let span =
+                            format_span(start.with_timezone(&Local), stop.with_timezone(&Local));
+                        println!("{}", row(12, &span, &cfg.format_duration(*worked)));
+                    }
+                }
+                for note in &d.notes {
+                    println!("{:12}{}", "", note.italic());
+                }
             }
-            cur_w
-        }) {
-            let dur: DurationDef = group
-                .fold(chrono::Duration::zero(), |dur, (_, d)| {
-                    dur.checked_add(&d.into()).unwrap()
-                })
-                .into();
-            println!("    Week {:2}: {:02}", week, dur);
         }
     }
-    Ok(())
+    if target.is_some() {
+        println!("{}", row(4, "Days under target:", &short_days.to_string()));
+    }
+    if !marked.is_empty() {
+        println!("    Days off:");
+        for (day, kind) in marked {
+            let label = format!("{}:", day_label(cfg, *day));
+            println!("{}", row(8, &label, &kind.to_string()));
+        }
+    }
+    for d in days.iter().filter(|d| d.lacks_break()) {
+        println!(
+            "    {} {}: worked {} with only {} of breaks.",
+            "Warning:".yellow(),
+            day_label(cfg, d.day),
+            cfg.format_duration(d.worked),
+            cfg.format_duration(d.breaks)
+        );
+    }
+    let total = rounded(
+        cfg,
+        days.iter().fold(Duration::zero(), |acc, d| acc + d.worked),
+    );
+    print_total(cfg, 4, "Total:", total);
+    if let Some(over) = over {
+        println!("{}", row(4, "Overtime:", &cfg.format_duration(over)));
+    }
+    total
 }
 
-/// Print current state of started work, running and finished breaks.
-fn show_state(balance: &TimeBalance) {
-    let break_state = balance.break_state();
-    let dur = if let Some((dur, start)) = balance.start_state() {
-        let total: DurationDef = (dur - break_state.sum).into();
+/// Print the state of started work, running and finished breaks at `now`.
+fn show_state(balance: &TimeBalance, now: DateTime<Utc>) {
+    let cfg = balance.config.as_ref().unwrap_or_default();
+    let today = now.with_timezone(&Local).date_naive();
+    let break_state = balance.break_state(now);
+    let dur = if let Some((dur, start)) = balance.start_state(now) {
         println!(
             "Started at {}, worked {} since then.",
             start.with_timezone(&chrono::Local).format("%H:%M"),
-            total
+            live_worked(cfg, dur - break_state.sum)
         );
+        if balance.idle(now).is_some() {
+            println!(
+                "{}",
+                "That's long ago, did you forget to stop? Run 'stempel stop' or 'stempel cancel'."
+                    .red()
+            );
+        }
         dur
     } else {
         Duration::zero()
@@ -131,39 +1022,38 @@ fn show_state(balance: &TimeBalance) {
         .iter()
         .fold(String::new(), |acc, (s, d)| {
             format!(
-                "{}{} for {:02}:{:02}h, ",
+                "{}{} for {}, ",
                 acc,
                 s.with_timezone(&Local).time().format("%H:%M"),
-                d.num_hours(),
-                d.num_minutes() % 60
+                cfg.format_duration(*d)
             )
         });
     let pause = if let Some(start) = break_state.current {
         println!(
-            "You're on a break since {}, with breaks at {}took {:02}:{:02}h.",
+            "You're on a break since {}, with breaks at {}took {}.",
             start.with_timezone(&chrono::Local).format("%H:%M"),
             break_str,
-            break_state.sum.num_hours(),
-            break_state.sum.num_minutes() % 60
+            cfg.format_duration(break_state.sum)
         );
         break_state.sum
     } else if break_state.sum > Duration::seconds(0) {
         println!(
-            "You had breaks at {}with a total of {:02}:{:02}h.",
+            "You had breaks at {}with a total of {}.",
             break_str,
-            break_state.sum.num_hours(),
-            break_state.sum.num_minutes() % 60
+            cfg.format_duration(break_state.sum)
         );
         break_state.sum
     } else {
         break_state.sum
     };
 
-    if let Some(daily) = balance.config.as_ref().unwrap_or_default().daily_hours {
+    if balance.is_day_off(today) {
+        println!("Today is a day off, every minute counts as overtime.");
+    } else if let Some(daily) = cfg.daily_hours {
         let daily = Duration::hours(daily as i64);
         let remaining = daily - dur + pause;
         let daily_range = balance
-            .daily_range(Local::now().date_naive(), Local)
+            .daily_range(today, Local)
             .unwrap() // TODO: get rid of unwrap
             .fold(Duration::seconds(0), |acc, (_, dur)| {
                 log::trace!("dur: {:?}", dur);
@@ -176,23 +1066,86 @@ fn show_state(balance: &TimeBalance) {
         );
         if remaining < Duration::zero() {
             println!(
-                "You're done for today. You have {:02}:{:02}h overhours.",
-                (-remaining).num_hours(),
-                (-remaining).num_minutes() % 60
+                "You're done for today. You have {} overhours.",
+                cfg.format_duration(-remaining)
             );
         } else if !(remaining - daily).is_zero() {
-            println!(
-                "You still need to work {:02}:{:02}h.",
-                remaining.num_hours(),
-                remaining.num_minutes() % 60,
-            );
+            println!("You still need to work {}.", cfg.format_duration(remaining));
         }
     }
     if let Some(hours) = balance.calculate_overhours() {
-        println!(
-            "You have total overhours of {:02}:{:02}h",
-            hours.num_hours(),
-            hours.num_minutes() % 60
-        );
+        match cfg.overtime_cap().filter(|c| hours.abs() >= *c) {
+            Some(cap) => println!(
+                "You have total overhours of {} (capped at {})",
+                cfg.format_duration(hours),
+                cfg.format_duration(cap)
+            ),
+            None => println!("You have total overhours of {}", cfg.format_duration(hours)),
+        }
+        if let Some((left, cap)) = cfg.cap_left(hours) {
+            println!(
+                "{}",
+                format!(
+                    "You're {} from the {} cap, take some time off before overtime is forfeited.",
+                    cfg.format_duration(left),
+                    cfg.format_duration(cap)
+                )
+                .yellow()
+            );
+        }
+    } else {
+        println!("{}", format!("{}.", daily_hours_unset()).dimmed());
     }
 }
+
+#[test]
+fn rows_align() {
+    let week = row(4, "Week  2:", "8:00h");
+    let day = row(
+        8,
+        &day_label(
+            &Config::default(),
+            NaiveDate::from_ymd_opt(2022, 1, 12).unwrap(),
+        ),
+        "123:45h",
+    );
+    assert_eq!(week.len(), LABEL_WIDTH + VALUE_WIDTH);
+    assert_eq!(week.len(), day.len());
+    assert!(day.starts_with("        Wednesday 12.01."));
+}
+
+#[test]
+fn round_to_quarters() {
+    let mut cfg = Config::default();
+    let dur = Duration::minutes(8 * 60 + 7);
+    assert_eq!(rounded(&cfg, dur), dur);
+    cfg.display_round_quarter = Some(true);
+    assert_eq!(rounded(&cfg, dur), Duration::hours(8));
+    assert_eq!(
+        rounded(&cfg, Duration::minutes(8 * 60 + 8)),
+        Duration::minutes(8 * 60 + 15)
+    );
+}
+
+#[test]
+fn live_worked_snaps_to_quarters() {
+    let mut cfg = Config::default();
+    let dur = Duration::seconds(7 * 3600 + 52 * 60 + 29);
+    assert_eq!(live_worked(&cfg, dur), "07:52h");
+    cfg.display_round_quarter = Some(true);
+    assert_eq!(live_worked(&cfg, dur), "07:45h");
+    assert_eq!(live_worked(&cfg, dur + Duration::seconds(1)), "08:00h");
+}
+
+#[test]
+fn progress_toward_daily_hours() {
+    let daily = Duration::hours(8);
+    assert_eq!(
+        progress_bar(Duration::minutes(192), daily),
+        ("[########------------] 40%".to_string(), 40)
+    );
+    assert_eq!(progress_bar(Duration::zero(), daily).1, 0);
+    let (bar, percent) = progress_bar(Duration::hours(10), daily);
+    assert_eq!(bar, format!("[{}] 125%", "#".repeat(20)));
+    assert_eq!(percent, 125);
+}
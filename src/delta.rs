@@ -1,42 +1,157 @@
 use crate::errors::*;
-use chrono::{DateTime, Duration, Timelike, Utc};
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+    Weekday,
+};
 
 pub fn parse_offset(src: &str) -> Result<DateTime<Utc>> {
+    offset_from(Utc::now(), parse_offset_duration(src)?)
+}
+
+/// Parse an offset in format `XX[h|m|s][+-]` to a signed duration.
+pub fn parse_offset_duration(src: &str) -> Result<Duration> {
     let sign_pos = src.ends_with('+');
     let stripped = src
         .strip_suffix(|p| p == '+' || p == '-')
         .ok_or_else(|| eyre!("Does not end with + or -"))?;
     let human = stripped.parse::<humantime::Duration>()?;
     let duration = chrono::Duration::from_std(*human)?;
-
-    let date_time: DateTime<Utc> = if sign_pos {
-        Utc::now().checked_add_signed(duration)
-    } else {
-        Utc::now().checked_sub_signed(duration)
-    }
-    .ok_or_else(|| eyre!("Could not convert {} to duration", duration))?;
     log::trace!(
-        "Deserialized {} to an offset {}min, timestamp {}",
+        "Deserialized {} to an offset {}min",
         src,
-        duration.num_minutes(),
-        date_time
+        duration.num_minutes()
     );
-    Ok(date_time)
+    Ok(if sign_pos { duration } else { -duration })
+}
+
+/// Time point `offset` away from `now`.
+pub fn offset_from(now: DateTime<Utc>, offset: Duration) -> Result<DateTime<Utc>> {
+    now.checked_add_signed(offset)
+        .ok_or_else(|| eyre!("Could not convert {} to duration", offset))
 }
 
 pub fn parse_time(src: &str) -> Result<DateTime<Utc>> {
-    let time = chrono::NaiveTime::parse_from_str(src, "%H:%M")?;
-    let date_time = chrono::Utc::now().date_naive().and_time(time);
-    let local = date_time.and_local_timezone(chrono::Local).unwrap();
-    let utc = DateTime::<Utc>::from(local);
-    log::trace!("Deserialized {} to a time point {}", src, date_time);
+    time_on(Utc::now(), parse_clock(src)?)
+}
+
+/// Time point of the local clock `time` on the day of `now`.
+pub fn time_on(now: DateTime<Utc>, time: chrono::NaiveTime) -> Result<DateTime<Utc>> {
+    let date_time = now.date_naive().and_time(time);
+    let utc = local_to_utc(date_time, &chrono::Local)?;
+    log::trace!("Resolved {} to a time point {}", time, date_time);
     Ok(utc)
 }
 
+/// Convert `date_time` in the time zone `tz` to UTC.
+///
+/// Picks the earliest of times repeated when daylight saving time ends and
+/// fails for times skipped when it starts.
+fn local_to_utc<Tz: TimeZone>(date_time: NaiveDateTime, tz: &Tz) -> Result<DateTime<Utc>> {
+    match date_time.and_local_timezone(tz.clone()) {
+        LocalResult::Single(t) => Ok(t.with_timezone(&Utc)),
+        LocalResult::Ambiguous(earliest, latest) => {
+            log::trace!(
+                "{} is ambiguous, using {:?} instead of {:?}",
+                date_time,
+                earliest,
+                latest
+            );
+            Ok(earliest.with_timezone(&Utc))
+        }
+        LocalResult::None => Err(usage_err!(
+            "{} does not exist in your time zone because of daylight saving time",
+            date_time.format("%H:%M")
+        )
+        .into()),
+    }
+}
+
+/// Parse a time of day in format `HH:MM` or 12-hour clock like `2:30pm`.
+///
+/// Errors of the 24-hour format are reported if neither format matches.
+pub fn parse_clock(src: &str) -> Result<chrono::NaiveTime> {
+    match chrono::NaiveTime::parse_from_str(src, "%H:%M") {
+        Ok(time) => Ok(time),
+        Err(e) => chrono::NaiveTime::parse_from_str(src, "%I:%M%p").or(Err(e.into())),
+    }
+}
+
+/// A day given on the command line. Relative days are resolved with
+/// `Day::on` once the current day is known, e.g. from `--now`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Day {
+    /// A calendar day like `2024-03-06`.
+    Date(NaiveDate),
+    /// `today` or `yesterday` as the number of days before today.
+    DaysAgo(u8),
+    /// The most recent weekday before today.
+    Last(Weekday),
+}
+
+impl Day {
+    /// The calendar day of this day relative to `today`.
+    ///
+    /// A weekday is its most recent occurrence before `today`, so `monday` on
+    /// a Monday is the Monday a week ago.
+    pub fn on(self, today: NaiveDate) -> Result<NaiveDate> {
+        let back = match self {
+            Day::Date(date) => return Ok(date),
+            Day::DaysAgo(days) => days.into(),
+            Day::Last(weekday) => {
+                (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday() - 1)
+                    % 7
+                    + 1
+            }
+        };
+        today
+            .checked_sub_signed(Duration::days(back.into()))
+            .ok_or_else(|| eyre!("There is no day {} days before {}", back, today))
+    }
+}
+
+/// Parse a day in format `YYYY-MM-DD` or `DD.MM.YYYY`, or as `today`,
+/// `yesterday` or a weekday like `monday` or `mon`.
+pub fn parse_day(src: &str) -> Result<Day> {
+    let lower = src.trim().to_lowercase();
+    let day = match lower.as_str() {
+        "today" => Some(Day::DaysAgo(0)),
+        "yesterday" => Some(Day::DaysAgo(1)),
+        _ => match lower.parse::<Weekday>() {
+            Ok(weekday) => Some(Day::Last(weekday)),
+            Err(_) => NaiveDate::parse_from_str(&lower, "%Y-%m-%d")
+                .or_else(|_| NaiveDate::parse_from_str(&lower, "%d.%m.%Y"))
+                .ok()
+                .map(Day::Date),
+        },
+    };
+    let day = day.ok_or_else(|| {
+        usage_err!(
+            "'{}' is no day, use 'YYYY-MM-DD', 'DD.MM.YYYY', 'today', 'yesterday' or a weekday",
+            src
+        )
+    })?;
+    log::trace!("Parsed {} to the day {:?}", src, day);
+    Ok(day)
+}
+
+/// Parse a day like `parse_day` and resolve it relative to `today`.
+pub fn date_on(today: NaiveDate, src: &str) -> Result<NaiveDate> {
+    parse_day(src)?.on(today)
+}
+
+/// Parse a duration either in format `HH:MM` or human readable like `1h30m`.
 pub fn parse_duration(src: &str) -> Result<Duration> {
-    let time = chrono::NaiveTime::parse_from_str(src, "%H:%M")?;
-    let duration =
-        chrono::Duration::hours(time.hour().into()) + Duration::minutes(time.minute().into());
+    let duration = match chrono::NaiveTime::parse_from_str(src, "%H:%M") {
+        Ok(time) => {
+            chrono::Duration::hours(time.hour().into()) + Duration::minutes(time.minute().into())
+        }
+        Err(_) => {
+            let human = src
+                .parse::<humantime::Duration>()
+                .map_err(|_| eyre!("'{}' is neither 'HH:MM' nor a duration like '1h30m'", src))?;
+            chrono::Duration::from_std(*human)?
+        }
+    };
     log::trace!(
         "Deserialized {} to a duration of {} min",
         src,
@@ -45,6 +160,15 @@ pub fn parse_duration(src: &str) -> Result<Duration> {
     Ok(duration)
 }
 
+/// Parse a duration like `parse_duration` with an optional leading sign,
+/// e.g. `-02:00`.
+pub fn parse_signed_duration(src: &str) -> Result<Duration> {
+    match src.strip_prefix('-') {
+        Some(abs) => Ok(-parse_duration(abs)?),
+        None => parse_duration(src.strip_prefix('+').unwrap_or(src)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -173,6 +297,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn deserialize_time_am_pm() {
+        let today = |h, m| {
+            Utc::now()
+                .date_naive()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_local_timezone(Local)
+                .unwrap()
+        };
+        assert_eq!(parse_time("2:30pm").unwrap(), today(14, 30));
+        assert_eq!(parse_time("2:30PM").unwrap(), today(14, 30));
+        assert_eq!(parse_time("12:00am").unwrap(), today(0, 0));
+        assert_eq!(parse_time("12:00pm").unwrap(), today(12, 0));
+        assert_eq!(parse_time("9:05Am").unwrap(), today(9, 5));
+        assert!(parse_time("13:00pm").is_err());
+    }
+
     #[test]
     fn deserialize_time_oob() {
         assert_eq!(
@@ -197,6 +339,88 @@ mod tests {
         );
     }
 
+    /// Central European time with the daylight saving time of 2022.
+    #[derive(Clone, Debug)]
+    struct Berlin;
+
+    impl Berlin {
+        fn offset(hours: i32) -> chrono::FixedOffset {
+            chrono::FixedOffset::east_opt(hours * 3600).unwrap()
+        }
+    }
+
+    impl TimeZone for Berlin {
+        type Offset = chrono::FixedOffset;
+
+        fn from_offset(_: &Self::Offset) -> Self {
+            Berlin
+        }
+
+        fn offset_from_local_date(&self, local: &chrono::NaiveDate) -> LocalResult<Self::Offset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(12, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<Self::Offset> {
+            let offsets: Vec<_> = [2, 1]
+                .into_iter()
+                .map(Berlin::offset)
+                .filter(|o| self.offset_from_utc_datetime(&(*local - *o)) == *o)
+                .collect();
+            match offsets[..] {
+                [o] => LocalResult::Single(o),
+                [a, b] => LocalResult::Ambiguous(a, b),
+                _ => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &chrono::NaiveDate) -> Self::Offset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> Self::Offset {
+            let summer = Utc
+                .with_ymd_and_hms(2022, 3, 27, 1, 0, 0)
+                .unwrap()
+                .naive_utc()
+                ..Utc
+                    .with_ymd_and_hms(2022, 10, 30, 1, 0, 0)
+                    .unwrap()
+                    .naive_utc();
+            if summer.contains(utc) {
+                Berlin::offset(2)
+            } else {
+                Berlin::offset(1)
+            }
+        }
+    }
+
+    #[test]
+    fn local_to_utc_dst() {
+        let local = |m, d, h, min| {
+            chrono::NaiveDate::from_ymd_opt(2022, m, d)
+                .unwrap()
+                .and_hms_opt(h, min, 0)
+                .unwrap()
+        };
+        assert_eq!(
+            local_to_utc(local(1, 12, 8, 0), &Berlin).unwrap(),
+            Utc.with_ymd_and_hms(2022, 1, 12, 7, 0, 0).unwrap()
+        );
+        assert!(local_to_utc(local(3, 27, 2, 30), &Berlin).is_err());
+        assert_eq!(
+            local_to_utc(local(3, 27, 3, 0), &Berlin).unwrap(),
+            Utc.with_ymd_and_hms(2022, 3, 27, 1, 0, 0).unwrap()
+        );
+        assert_eq!(
+            local_to_utc(local(10, 30, 2, 30), &Berlin).unwrap(),
+            Utc.with_ymd_and_hms(2022, 10, 30, 0, 30, 0).unwrap()
+        );
+        assert_eq!(
+            local_to_utc(local(10, 30, 3, 30), &Berlin).unwrap(),
+            Utc.with_ymd_and_hms(2022, 10, 30, 2, 30, 0).unwrap()
+        );
+    }
+
     #[test]
     fn deserialize_duration() {
         assert_eq!(parse_duration("0:45").unwrap(), Duration::minutes(45));
@@ -204,5 +428,100 @@ mod tests {
         assert_eq!(parse_duration("0:5").unwrap(), Duration::minutes(5));
         assert_eq!(parse_duration("10:0").unwrap(), Duration::minutes(600));
         assert_eq!(parse_duration("0:45").unwrap(), Duration::minutes(45));
+        assert_eq!(parse_duration("1:30").unwrap(), Duration::minutes(90));
+    }
+
+    #[test]
+    fn deserialize_signed_duration() {
+        assert_eq!(
+            parse_signed_duration("-02:00").unwrap(),
+            Duration::hours(-2)
+        );
+        assert_eq!(
+            parse_signed_duration("+1h30m").unwrap(),
+            Duration::minutes(90)
+        );
+        assert_eq!(
+            parse_signed_duration("0:45").unwrap(),
+            Duration::minutes(45)
+        );
+        assert!(parse_signed_duration("--1:00").is_err());
+    }
+
+    #[test]
+    fn deserialize_human_duration() {
+        assert_eq!(parse_duration("90m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("45s").unwrap(), Duration::seconds(45));
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("-1:30").is_err());
+    }
+
+    #[test]
+    fn offset_and_time_relative_to_now() {
+        assert_eq!(
+            parse_offset_duration("1h30m+").unwrap(),
+            Duration::minutes(90)
+        );
+        assert_eq!(
+            parse_offset_duration("15m-").unwrap(),
+            Duration::minutes(-15)
+        );
+        assert!(parse_offset_duration("15m").is_err());
+
+        let now = Utc.with_ymd_and_hms(2022, 1, 12, 12, 0, 0).unwrap();
+        assert_eq!(
+            offset_from(now, Duration::minutes(-15)).unwrap(),
+            Utc.with_ymd_and_hms(2022, 1, 12, 11, 45, 0).unwrap()
+        );
+        let time = time_on(now, chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap()).unwrap();
+        assert_eq!(time.with_timezone(&chrono::Local).time().hour(), 12);
+    }
+
+    #[test]
+    fn dates_in_several_formats() {
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        // a Wednesday
+        let today = day(2024, 3, 6);
+        assert_eq!(date_on(today, "2024-02-29").unwrap(), day(2024, 2, 29));
+        assert_eq!(date_on(today, "29.02.2024").unwrap(), day(2024, 2, 29));
+        assert_eq!(date_on(today, "1.3.2024").unwrap(), day(2024, 3, 1));
+        assert_eq!(date_on(today, "today").unwrap(), today);
+        assert_eq!(date_on(today, "Today").unwrap(), today);
+        assert_eq!(date_on(today, "yesterday").unwrap(), day(2024, 3, 5));
+
+        assert_eq!(date_on(today, "tuesday").unwrap(), day(2024, 3, 5));
+        assert_eq!(date_on(today, "Mon").unwrap(), day(2024, 3, 4));
+        assert_eq!(date_on(today, "wednesday").unwrap(), day(2024, 2, 28));
+        assert_eq!(date_on(today, "thu").unwrap(), day(2024, 2, 29));
+        assert_eq!(date_on(today, "sunday").unwrap(), day(2024, 3, 3));
+
+        assert!(date_on(today, "2024-02-30").is_err());
+        assert!(date_on(today, "31.04.2024").is_err());
+        assert!(date_on(today, "tomorrow").is_err());
+        assert!(date_on(today, "").is_err());
+    }
+
+    #[test]
+    fn yesterday_across_boundaries() {
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(
+            date_on(day(2024, 3, 1), "yesterday").unwrap(),
+            day(2024, 2, 29)
+        );
+        assert_eq!(
+            date_on(day(2023, 3, 1), "yesterday").unwrap(),
+            day(2023, 2, 28)
+        );
+        assert_eq!(
+            date_on(day(2024, 1, 1), "yesterday").unwrap(),
+            day(2023, 12, 31)
+        );
+        assert_eq!(
+            date_on(day(2024, 5, 1), "yesterday").unwrap(),
+            day(2024, 4, 30)
+        );
+        // 2024-01-01 is a Monday
+        assert_eq!(date_on(day(2024, 1, 1), "fri").unwrap(), day(2023, 12, 29));
     }
 }
@@ -1,8 +1,11 @@
 //! Errors for stempel.
 
+use chrono::{DateTime, Duration, Local, Utc};
 pub use color_eyre::eyre::{bail, eyre, Error, Result, WrapErr};
 use thiserror::Error;
 
+use std::path::PathBuf;
+
 #[derive(Debug, Error)]
 pub struct UsageError(pub String);
 
@@ -12,9 +15,128 @@ impl std::fmt::Display for UsageError {
     }
 }
 
+/// Errors of the storage and of tracking work, library consumers can match on
+/// them with `err.downcast_ref::<TimeError>()`.
+#[derive(Debug, Error)]
+pub enum TimeError {
+    /// There is no storage at the path.
+    StorageMissing(PathBuf),
+    /// The storage isn't valid json, `hint` names a backup to restore.
+    StorageCorrupt {
+        path: PathBuf,
+        reason: String,
+        hint: String,
+    },
+    /// The storage needs to be migrated from the legacy format.
+    LegacyStorage(PathBuf),
+    /// Stopping or taking a break without a running work.
+    NotStarted,
+    /// Starting while the work started at the time is running.
+    AlreadyStarted(DateTime<Utc>),
+    /// Starting a break while the break started at the time is running.
+    AlreadyOnBreak(DateTime<Utc>),
+    /// Starting work while on the break started at the time.
+    OnBreak(DateTime<Utc>),
+    /// Stopping work at `stop` while on the break started at `since`.
+    StopOnBreak {
+        since: DateTime<Utc>,
+        stop: DateTime<Utc>,
+    },
+    /// Finishing a break while none is running, with the start and duration
+    /// of the last finished break.
+    NotOnBreak(Option<(DateTime<Utc>, Duration)>),
+    /// Starting an `action` at a time too far in the future.
+    InFuture {
+        action: &'static str,
+        time: DateTime<Utc>,
+    },
+    /// Stopping work or a break before its start.
+    StopBeforeStart {
+        start: DateTime<Utc>,
+        stop: DateTime<Utc>,
+    },
+    /// The breaks add up to more than the work.
+    BreakLongerThanWork,
+    /// There is neither a running break nor work to cancel.
+    NothingToCancel,
+}
+
+impl std::fmt::Display for TimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hm = |t: &DateTime<Utc>| t.with_timezone(&Local).format("%H:%M");
+        match self {
+            TimeError::StorageMissing(p) => write!(
+                f,
+                "Storage '{}' does not exist, run 'stempel init' first.",
+                p.display()
+            ),
+            TimeError::StorageCorrupt { path, reason, hint } => write!(
+                f,
+                "Storage '{}' is corrupt: {}. {}",
+                path.display(),
+                reason,
+                hint
+            ),
+            TimeError::LegacyStorage(p) => write!(
+                f,
+                "Storage '{}' is in the legacy format, run 'stempel migrate' to convert it.",
+                p.display()
+            ),
+            TimeError::NotStarted => write!(f, "You did not start working"),
+            TimeError::AlreadyStarted(s) => write!(f, "You already started at {}", hm(s)),
+            TimeError::AlreadyOnBreak(s) => write!(f, "You're already on a break since {}", hm(s)),
+            TimeError::OnBreak(s) => write!(
+                f,
+                "You're on a break since {}, run 'stempel resume' to continue working.",
+                hm(s)
+            ),
+            TimeError::StopOnBreak { since, stop } => write!(
+                f,
+                "You're on a break since {}, won't stop your current work. Run 'stempel stop --time {} --end-break' to end the break and your work at {}, or 'stempel break stop --time HH:MM' first if the break ended earlier.",
+                hm(since),
+                hm(stop),
+                hm(stop)
+            ),
+            TimeError::NotOnBreak(Some((s, d))) => write!(
+                f,
+                "You're not on a break right now, your last break started at {} and took {:02}:{:02}. Run 'stempel break list' to see all breaks.",
+                hm(s),
+                d.num_hours(),
+                d.num_minutes() % 60
+            ),
+            TimeError::NotOnBreak(None) => write!(f, "You're not on a break right now."),
+            TimeError::InFuture { action, time } => {
+                write!(f, "You can't {} in the future at {}", action, hm(time))
+            }
+            TimeError::StopBeforeStart { start, stop } => {
+                write!(f, "Stop time {} is before start {}", hm(stop), hm(start))
+            }
+            TimeError::BreakLongerThanWork => write!(f, "Your break was longer than your work"),
+            TimeError::NothingToCancel => write!(f, "Nothing to cancel"),
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! usage_err {
     ($($arg:tt)*) => {
         UsageError(format!($($arg)*))
     };
 }
+
+/// Error of a command which needs the daily working hours, e.g. to calculate
+/// the overtime.
+pub fn daily_hours_unset() -> UsageError {
+    usage_err!(
+        "Overtime is calculated from your daily working hours, set them with 'stempel configure --daily-hours <HOURS>'"
+    )
+}
+
+/// Error of a command which needs the disabled cargo `feature`.
+pub fn feature_disabled(feature: &str) -> UsageError {
+    usage_err!(
+        "stempel was built without the '{}' feature, rebuild it with '--features {}'",
+        feature,
+        feature
+    )
+}
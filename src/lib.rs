@@ -3,9 +3,14 @@
 #[macro_use]
 pub mod errors;
 
+mod backup;
 mod balance;
-mod cli_input;
+pub mod cli_input;
 pub mod commands;
 pub mod delta;
 pub mod month;
 mod storage;
+mod tracker;
+
+pub use balance::DayKind;
+pub use tracker::{WorkState, WorkTracker};
@@ -1,52 +1,319 @@
-use log::debug;
-use std::path::PathBuf;
+use log::{debug, warn};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 use stempel::commands;
-use stempel::errors::UsageError;
+use stempel::errors::{TimeError, UsageError};
 
 mod clap_cli;
 use clap_cli::*;
 
+/// Directory of the default storage and the profiles.
+///
+/// Without a config dir and `HOME`, e.g. in minimal containers, the current
+/// working directory or the temp dir is used instead.
+fn storage_dir() -> PathBuf {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        if let Some(dir) = dirs::config_dir() {
+            return dir;
+        }
+        if let Some(home) = std::env::var_os("HOME").filter(|h| !h.is_empty()) {
+            return PathBuf::from(home);
+        }
+        let dir = std::env::current_dir().unwrap_or_else(|_| std::env::temp_dir());
+        warn!(
+            "No config dir and no HOME found, storing in {}. Use --storage or {} to choose a path.",
+            dir.display(),
+            STORAGE_ENV
+        );
+        dir
+    })
+    .clone()
+}
+
+/// Environment variable overriding the default storage.
+const STORAGE_ENV: &str = "STEMPEL_STORAGE";
+
+/// Resolve the storage file from an explicit `storage` path or a `profile`.
+///
+/// Without both, the storage in `STEMPEL_STORAGE` is used if set, otherwise
+/// the default profile configured in the default storage or the default
+/// storage itself. Returns the path and where it came from.
+fn resolve_storage(storage: Option<PathBuf>, profile: Option<String>) -> (PathBuf, &'static str) {
+    if let Some(s) = storage {
+        return (s, "--storage");
+    }
+    if let Some(p) = profile {
+        return (
+            storage_dir().join(commands::config::profile_file(&p)),
+            "--profile",
+        );
+    }
+    if let Some(env) = std::env::var_os(STORAGE_ENV).filter(|e| !e.is_empty()) {
+        return (PathBuf::from(env), STORAGE_ENV);
+    }
+    let dir = storage_dir();
+    let default_path = dir.join(commands::config::DEFAULT_STORAGE);
+    match commands::config::default_profile(&default_path) {
+        Some(p) => (
+            dir.join(commands::config::profile_file(&p)),
+            "default profile",
+        ),
+        None => (default_path, "default"),
+    }
+}
+
+/// Print the effective `storage`, where it came from and its size and last
+/// modification if it exists.
+fn whereis(storage: &Path, origin: &str) -> color_eyre::Result<()> {
+    println!("Storage: {} (from {})", storage.display(), origin);
+    match std::fs::metadata(storage) {
+        Ok(meta) => {
+            let modified: chrono::DateTime<chrono::Local> = meta.modified()?.into();
+            println!(
+                "Size: {} bytes, last modified {}",
+                meta.len(),
+                modified.format("%d.%m.%Y %H:%M")
+            );
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            println!("It does not exist yet, run 'stempel init' to create it.")
+        }
+        Err(e) => return Err(e.into()),
+    }
+    Ok(())
+}
+
 fn run() -> color_eyre::Result<()> {
     let clap = Cli::parse();
 
-    let fallback = PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| "/".to_string()));
-    let default_path = dirs::config_dir().unwrap_or(fallback).join("stempel.json");
-
-    let storage = clap.storage.unwrap_or(default_path);
+    let (storage, origin) = resolve_storage(clap.storage, clap.profile);
+    let dry_run = clap.dry_run;
+    stempel::cli_input::YesNo::assume_yes(clap.yes);
+    let now = clap.now.unwrap_or_else(chrono::Utc::now);
+    let today = now.with_timezone(&chrono::Local).date_naive();
+    let on_today = |day: Option<stempel::delta::Day>| day.map(|d| d.on(today)).transpose();
+    commands::stats::warn_if_idle(&storage, now);
     match clap.command {
-        Commands::Start(timings) => {
-            let time_pt = timings.time();
+        Commands::Start { timings, force } => {
+            let time_pt = timings.time(now)?;
             debug!("Start at {}, store in {:?}", time_pt, storage);
-            commands::control::start(storage, time_pt)?;
+            commands::control::start(storage, time_pt, now, force, dry_run)?;
         }
-        Commands::Stop(timings) => {
-            let time_pt = timings.time();
+        Commands::Stop {
+            note,
+            tags,
+            duration: Some(dur),
+            gross,
+            ..
+        } => {
+            debug!("Stop after {:?}, store in {:?}", dur, storage);
+            commands::control::stop_after(storage, dur, gross, note, tags, now, dry_run)?;
+        }
+        Commands::Stop {
+            timings,
+            note,
+            tags,
+            end_break,
+            ..
+        } => {
+            let time_pt = timings.time(now)?;
             debug!("Stop at {:?}, store in {:?}", time_pt, storage);
-            commands::control::stop(storage, time_pt)?;
+            commands::control::stop(
+                storage,
+                time_pt,
+                note,
+                tags,
+                end_break,
+                timings.is_explicit(),
+                dry_run,
+            )?;
         }
         Commands::Break(startstop) => match startstop {
             clap_cli::StartStop::Start(t) => {
-                commands::control::start_break(storage, t.time(), true)?
+                commands::control::start_break(storage, t.time(now)?, now, true, dry_run)?
+            }
+            clap_cli::StartStop::Stop(t) => {
+                commands::control::stop_break(storage, t.time(now)?, true, dry_run)?
+            }
+            clap_cli::StartStop::List => commands::stats::break_list(storage, now)?,
+            clap_cli::StartStop::Cancel { time } => {
+                commands::control::cancel_break(storage, time, dry_run)?
+            }
+            clap_cli::StartStop::Add {
+                date,
+                time,
+                duration,
+                force,
+            } => {
+                let at = on_today(date)?.zip(time);
+                commands::control::add_break(storage, at, duration, force, now, dry_run)?
+            }
+            clap_cli::StartStop::Duration { dur } => {
+                commands::control::take_break(storage, dur, now, dry_run)?
             }
-            clap_cli::StartStop::Stop(t) => commands::control::stop_break(storage, t.time(), true)?,
-            clap_cli::StartStop::Duration { dur } => commands::control::take_break(storage, dur)?,
         },
-        Commands::Cancel => {
-            debug!("Cancel");
-            commands::control::cancel(storage)?;
+        Commands::Work => {
+            debug!("Work in foreground, store in {:?}", storage);
+            commands::control::work(storage, now, dry_run)?;
+        }
+        Commands::Continue => {
+            debug!("Continue, store in {:?}", storage);
+            commands::control::continue_work(storage, dry_run)?;
         }
-        Commands::Stats { month } => {
-            debug!("Stats of `{:?}`", month);
-            commands::stats::stats(storage, month)?;
+        Commands::Pause(timings) => {
+            let time_pt = timings.time(now)?;
+            debug!("Pause at {}, store in {:?}", time_pt, storage);
+            commands::control::pause(storage, time_pt, now, dry_run)?;
         }
-        Commands::Migrate => {
-            debug!("Migrate, stored in {:?}", storage);
-            commands::control::migrate(storage)?;
+        Commands::Resume(timings) => {
+            let time_pt = timings.time(now)?;
+            debug!("Resume at {}, store in {:?}", time_pt, storage);
+            commands::control::resume(storage, time_pt, dry_run)?;
         }
-        Commands::Configure => {
-            debug!("Configure, stored in {:?}", storage);
-            commands::config::configure(storage)?;
+        Commands::Cancel { all } => {
+            debug!("Cancel, all: {}", all);
+            commands::control::cancel(storage, all, dry_run)?;
+        }
+        Commands::Stats {
+            month,
+            json,
+            total,
+            daily,
+            week,
+            since,
+            until,
+            months,
+            periods,
+            tags,
+            untagged,
+        } => {
+            let opts = commands::stats::StatsOptions {
+                month,
+                json,
+                total,
+                daily,
+                week,
+                since: on_today(since)?,
+                until: on_today(until)?,
+                months,
+                periods,
+                tags,
+                untagged,
+            };
+            debug!("Stats with {:?}", opts);
+            commands::stats::stats(storage, opts, now, dry_run)?;
+        }
+        Commands::Overtime { adjust, history } => {
+            debug!("Overtime adjusted by {:?}, stored in {:?}", adjust, storage);
+            commands::control::overtime(storage, adjust, history, now, dry_run)?;
+        }
+        Commands::Canonicalize => {
+            debug!("Canonicalize, stored in {:?}", storage);
+            commands::control::canonicalize(storage, dry_run)?;
+        }
+        Commands::Doctor { fix } => {
+            debug!("Doctor, fix {}, stored in {:?}", fix, storage);
+            commands::control::doctor(storage, fix, now, dry_run)?;
+        }
+        Commands::Export {
+            format,
+            month,
+            since,
+            until,
+        } => {
+            let (since, until) = (on_today(since)?, on_today(until)?);
+            debug!(
+                "Export {:?} of {:?} from {:?} to {:?}",
+                format, month, since, until
+            );
+            commands::export::export(storage, format, month, since, until, now)?;
+        }
+        Commands::Report {
+            format,
+            month,
+            output,
+        } => {
+            debug!("Report {:?} of {:?} to {:?}", format, month, output);
+            commands::report::report(storage, format, month, output, now)?;
+        }
+        Commands::Import { format, input } => {
+            debug!("Import {:?} from {:?} into {:?}", format, input, storage);
+            commands::import::import(storage, format, &input, dry_run)?;
+        }
+        Commands::Digest { week } => {
+            debug!("Digest, week {:?}, stored in {:?}", week, storage);
+            commands::digest::digest(storage, week, now)?;
+        }
+        Commands::Check => {
+            debug!("Check, stored in {:?}", storage);
+            commands::stats::check(storage, now)?;
+        }
+        Commands::Today => {
+            debug!("Today, stored in {:?}", storage);
+            commands::stats::today(storage, now, dry_run)?;
+        }
+        Commands::Standup => {
+            debug!("Standup, stored in {:?}", storage);
+            commands::stats::standup(storage, now)?;
+        }
+        Commands::Breaks { date } => {
+            let date = on_today(date)?.unwrap_or(today);
+            debug!("Breaks of {}", date);
+            commands::stats::breaks(storage, date)?;
+        }
+        Commands::Holiday { date } => {
+            let date = date.on(today)?;
+            debug!("Holiday on {}, stored in {:?}", date, storage);
+            commands::config::mark_day(storage, date, stempel::DayKind::Holiday, dry_run)?;
+        }
+        Commands::Vacation { date } => {
+            let date = date.on(today)?;
+            debug!("Vacation on {}, stored in {:?}", date, storage);
+            commands::config::mark_day(storage, date, stempel::DayKind::Vacation, dry_run)?;
+        }
+        Commands::Mark { date, kind } => {
+            let date = date.on(today)?;
+            debug!("Mark {} as {:?}, stored in {:?}", date, kind, storage);
+            commands::config::mark_day(storage, date, kind, dry_run)?;
+        }
+        Commands::Migrate { backup_dir } => {
+            debug!(
+                "Migrate, stored in {:?}, backups in {:?}",
+                storage, backup_dir
+            );
+            commands::control::migrate(storage, backup_dir, dry_run)?;
+        }
+        Commands::Restore => {
+            debug!("Restore {:?}", storage);
+            commands::control::restore(storage, dry_run)?;
+        }
+        Commands::Profiles {
+            set_default,
+            clear_default,
+        } => {
+            let default_path = storage_dir().join(commands::config::DEFAULT_STORAGE);
+            if clear_default {
+                commands::config::set_default_profile(default_path, None, dry_run)?;
+            } else if set_default.is_some() {
+                commands::config::set_default_profile(default_path, set_default, dry_run)?;
+            } else {
+                commands::config::profiles(storage_dir(), &default_path)?;
+            }
+        }
+        Commands::Whereis => whereis(&storage, origin)?,
+        Commands::Init => {
+            debug!("Init {:?}", storage);
+            commands::config::init(storage, now, dry_run)?;
+        }
+        Commands::Configure(opts) => {
+            debug!("Configure with {:?}, stored in {:?}", opts, storage);
+            commands::config::configure(storage, opts, now, dry_run)?;
+        }
+        Commands::Config(ConfigCommand::Show { json, defaults }) => {
+            debug!("Show config of {:?}", storage);
+            commands::config::show(storage, json, defaults)?;
         }
         Commands::Completions { shell } => {
             debug!("Generating shell completions for {}", shell);
@@ -61,12 +328,15 @@ fn run() -> color_eyre::Result<()> {
 }
 
 fn main() -> color_eyre::Result<()> {
-    env_logger::init();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
     color_eyre::install()?;
     if let Err(e) = run() {
         if let Some(inner) = e.downcast_ref::<UsageError>() {
             log::error!("{}", inner);
             std::process::exit(1);
+        } else if let Some(inner) = e.downcast_ref::<TimeError>() {
+            log::error!("{}", inner);
+            std::process::exit(1);
         } else {
             Err(e)
         }
@@ -23,18 +23,31 @@ pub enum Month {
     December,
 }
 
+/// All month names `Month::from_str` accepts, in lower case. Numbers from
+/// `1` to `12` and the first three letters of a name are accepted as well.
+pub const POSSIBLE_VALUES: [&str; 14] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+    "current",
+    "now",
+];
+
 impl fmt::Display for Month {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
-impl From<String> for Month {
-    fn from(m: String) -> Self {
-        Month::from_str(m.as_str()).expect("failed to convert string to month")
-    }
-}
-
 impl Sub for Month {
     type Output = Self;
 
@@ -49,7 +62,19 @@ impl FromStr for Month {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
+        if let Ok(n) = s.parse::<u8>() {
+            return Month::try_from(n).map_err(|_| format!("Failed to parse '{}' into month", s));
+        }
+        let lower = s.to_lowercase();
+        if lower.len() == 3 {
+            if let Some(i) = POSSIBLE_VALUES[..12]
+                .iter()
+                .position(|m| m.starts_with(&lower))
+            {
+                return Month::try_from(i as u8 + 1).map_err(|e| e.to_string());
+            }
+        }
+        match lower.as_str() {
             "january" => Ok(Month::January),
             "february" => Ok(Month::February),
             "march" => Ok(Month::March),
@@ -72,12 +97,6 @@ impl FromStr for Month {
     }
 }
 
-impl From<&str> for Month {
-    fn from(other: &str) -> Self {
-        Self::from_str(other).unwrap()
-    }
-}
-
 #[test]
 fn display() {
     let jan = Month::January;
@@ -85,20 +104,18 @@ fn display() {
 }
 
 #[test]
-fn from_is_ok() {
-    let string = "January".to_string();
-    assert_eq!(Month::from(string), Month::January);
-    let string = "december".to_string();
-    assert_eq!(Month::from(string), Month::December);
-    let string = "AprIL".to_string();
-    assert_eq!(Month::from(string), Month::April);
+fn parse_is_ok() {
+    assert_eq!("January".parse::<Month>(), Ok(Month::January));
+    assert_eq!("december".parse::<Month>(), Ok(Month::December));
+    assert_eq!("AprIL".parse::<Month>(), Ok(Month::April));
 }
 
 #[test]
-#[should_panic(expected = "failed to convert string to month")]
-fn from_panics() {
-    let bad_month = "some".to_string();
-    let _ = Month::from(bad_month);
+fn parse_fails() {
+    assert_eq!(
+        "some".parse::<Month>(),
+        Err("Failed to parse 'some' into month".to_string())
+    );
 }
 
 #[test]
@@ -111,6 +128,13 @@ fn try_from_primitive() {
     assert!(Month::try_from(13).is_err());
 }
 
+#[test]
+fn possible_values_parse() {
+    for v in POSSIBLE_VALUES {
+        assert!(Month::from_str(v).is_ok());
+    }
+}
+
 #[test]
 fn from_str_works() {
     let m = "mAy";
@@ -118,3 +142,20 @@ fn from_str_works() {
     let m = "something";
     assert!(Month::from_str(m).is_err());
 }
+
+#[test]
+fn from_str_numbers_and_abbreviations() {
+    assert_eq!(Month::from_str("1"), Ok(Month::January));
+    assert_eq!(Month::from_str("6"), Ok(Month::June));
+    assert_eq!(Month::from_str("12"), Ok(Month::December));
+    assert_eq!(
+        Month::from_str("13"),
+        Err("Failed to parse '13' into month".to_string())
+    );
+    assert!(Month::from_str("0").is_err());
+    assert_eq!(Month::from_str("jan"), Ok(Month::January));
+    assert_eq!(Month::from_str("Sep"), Ok(Month::September));
+    assert_eq!(Month::from_str("DEC"), Ok(Month::December));
+    assert!(Month::from_str("ja").is_err());
+    assert!(Month::from_str("jun2").is_err());
+}
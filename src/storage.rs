@@ -133,10 +133,7 @@ impl WorkStorage {
             .cloned();
         match start {
             Some(s) => Ok(s),
-            None => bail!(usage_err!(
-                "You want to stop but you never started, strange work ethics, {}",
-                self.name
-            )),
+            None => bail!(TimeError::NotStarted),
         }
     }
 
@@ -149,7 +146,7 @@ impl WorkStorage {
             .cloned();
         match breaked {
             Some(s) => Ok(s),
-            None => bail!(usage_err!("You deserve that break")),
+            None => bail!(TimeError::NotOnBreak(None)),
         }
     }
 }
@@ -0,0 +1,197 @@
+//! Public interface to track work from other crates.
+//!
+//! `WorkTracker` wraps one storage file and offers the core operations on
+//! plain chrono types. Changes are only written by `WorkTracker::save`.
+
+use crate::balance::{entry_day, TimeBalance};
+use crate::errors::*;
+
+use chrono::{DateTime, Duration, Month, NaiveDate, Utc};
+use itertools::Itertools;
+
+use std::path::{Path, PathBuf};
+
+/// Tracks work in a storage file created by `stempel init`.
+#[derive(Debug)]
+pub struct WorkTracker {
+    storage: PathBuf,
+    balance: TimeBalance,
+}
+
+/// Snapshot of the running work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkState {
+    /// Start of the running work, if any.
+    pub started: Option<DateTime<Utc>>,
+    /// Time worked since the start without breaks.
+    pub worked: Duration,
+    /// Start of the running break, if any.
+    pub on_break_since: Option<DateTime<Utc>>,
+    /// Sum of all breaks since the start, including the running one.
+    pub breaks: Duration,
+}
+
+impl WorkTracker {
+    /// Read the existing `storage`.
+    pub fn open<P: AsRef<Path>>(storage: P) -> Result<Self> {
+        let balance = TimeBalance::from_file(&storage, false)?;
+        Ok(Self {
+            storage: storage.as_ref().to_path_buf(),
+            balance,
+        })
+    }
+
+    /// Start working at `time`, fails if already started, on a break or `time`
+    /// lies in the future of `now`.
+    pub fn start(&mut self, time: DateTime<Utc>, now: DateTime<Utc>) -> Result<()> {
+        if let Some(b) = self.balance.break_state(time).current {
+            bail!(TimeError::OnBreak(b));
+        }
+        self.balance.start(time, now)?;
+        self.balance.canocicalize()
+    }
+
+    /// Stop working at `time`, returns the time worked without breaks.
+    pub fn stop(&mut self, time: DateTime<Utc>) -> Result<Duration> {
+        self.stop_with_note(time, None)
+    }
+
+    /// Stop working at `time` and annotate the entry with `note`.
+    pub fn stop_with_note(
+        &mut self,
+        time: DateTime<Utc>,
+        note: Option<String>,
+    ) -> Result<Duration> {
+        let duration = self.balance.stop(time, note)?;
+        self.balance.canocicalize()?;
+        Ok(duration)
+    }
+
+    /// Stop working at `time`, annotate the entry with `note` and tag it with
+    /// `tags`, e.g. the client the work was for. An `explicit` time isn't
+    /// confirmed if the work started on an earlier day.
+    pub fn stop_tagged(
+        &mut self,
+        time: DateTime<Utc>,
+        note: Option<String>,
+        tags: Vec<String>,
+        explicit: bool,
+    ) -> Result<Duration> {
+        let duration = self.balance.stop_tagged(time, note, tags, explicit)?;
+        self.balance.canocicalize()?;
+        Ok(duration)
+    }
+
+    /// Stop working after `dur` of work, fails if that lies after `now`, see
+    /// `TimeBalance::stop_after`.
+    pub fn stop_after(
+        &mut self,
+        dur: Duration,
+        gross: bool,
+        note: Option<String>,
+        tags: Vec<String>,
+        now: DateTime<Utc>,
+    ) -> Result<Duration> {
+        let duration = self.balance.stop_after(dur, gross, note, tags, now)?;
+        self.balance.canocicalize()?;
+        Ok(duration)
+    }
+
+    /// Finish the running break at `time`, returns its duration.
+    pub fn finish_break(&mut self, time: DateTime<Utc>) -> Result<Duration> {
+        self.balance.finish_break(time)
+    }
+
+    /// State of the running work and breaks.
+    pub fn current_state(&self) -> WorkState {
+        self.state_at(Utc::now())
+    }
+
+    /// State of the running work and breaks at `now`.
+    pub fn state_at(&self, now: DateTime<Utc>) -> WorkState {
+        let break_state = self.balance.break_state(now);
+        let (started, worked) = match self.balance.start_state(now) {
+            Some((dur, start)) => (Some(start), dur - break_state.sum),
+            None => (None, Duration::zero()),
+        };
+        WorkState {
+            started,
+            worked,
+            on_break_since: break_state.current,
+            breaks: break_state.sum,
+        }
+    }
+
+    /// Time worked on each local day of `month` in `year`, days without work
+    /// are omitted.
+    pub fn month_totals(&self, year: i32, month: Month) -> Result<Vec<(NaiveDate, Duration)>> {
+        let totals = self
+            .balance
+            .month_range(year, month)?
+            .map(|(k, e)| (entry_day(k), Duration::from(e)))
+            .group_by(|(day, _)| *day)
+            .into_iter()
+            .map(|(day, group)| (day, group.fold(Duration::zero(), |acc, (_, d)| acc + d)))
+            .collect();
+        Ok(totals)
+    }
+
+    /// Write all changes to the storage.
+    pub fn save(&self) -> Result<()> {
+        self.balance.to_file(&self.storage)
+    }
+
+    /// The underlying balance, e.g. to access the configuration.
+    pub(crate) fn balance(&self) -> &TimeBalance {
+        &self.balance
+    }
+}
+
+#[test]
+fn track_work() {
+    use chrono::{Datelike, TimeZone};
+
+    let storage = std::env::temp_dir().join(format!("stempel-tracker-{}.json", std::process::id()));
+    let err = WorkTracker::open(&storage).expect_err("opening a missing storage fails");
+    assert!(matches!(
+        err.downcast_ref::<TimeError>(),
+        Some(TimeError::StorageMissing(_))
+    ));
+    TimeBalance::new().to_file(&storage).expect("writing works");
+
+    let start = Utc.with_ymd_and_hms(2022, 1, 12, 8, 0, 0).unwrap();
+    let mut tracker = WorkTracker::open(&storage).expect("opening works");
+    let err = tracker
+        .stop(start)
+        .expect_err("stopping before a start fails");
+    assert!(matches!(
+        err.downcast_ref::<TimeError>(),
+        Some(TimeError::NotStarted)
+    ));
+    tracker.start(start, start).expect("starting works");
+    assert_eq!(tracker.current_state().started, Some(start));
+    let err = tracker
+        .start(start, start)
+        .expect_err("starting twice fails");
+    assert!(matches!(
+        err.downcast_ref::<TimeError>(),
+        Some(TimeError::AlreadyStarted(s)) if *s == start
+    ));
+    assert_eq!(
+        tracker
+            .stop(start + Duration::hours(8))
+            .expect("stopping works"),
+        Duration::hours(8)
+    );
+    tracker.save().expect("saving works");
+
+    let tracker = WorkTracker::open(&storage).expect("opening works");
+    assert_eq!(tracker.current_state().started, None);
+    let totals = tracker
+        .month_totals(2022, Month::January)
+        .expect("totals work");
+    assert_eq!(totals.len(), 1);
+    assert_eq!(totals[0].0.month(), 1);
+    assert_eq!(totals[0].1, Duration::hours(8));
+    std::fs::remove_file(&storage).expect("removing works");
+}
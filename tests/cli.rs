@@ -0,0 +1,670 @@
+//! End-to-end tests driving the `stempel` binary against a temporary storage.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Empty storage with eight daily working hours.
+const STORAGE: &str = r#"{"name":"test","start":null,"breaks":[],"account":{},"config":{"month_stats":1,"daily_hours":8}}"#;
+
+/// Storage in the format before v0.10.0 with one day of work.
+const LEGACY: &str = r#"{"name":"test","work_sets":[{"ty":"Work","duration":{"secs":28800,"nanos":0},"start":"2024-03-05T09:00:00Z"}]}"#;
+
+/// Temporary dir holding an empty storage, removed on drop.
+fn storage() -> (TempDir, PathBuf) {
+    let dir = tempfile::tempdir().expect("Temp dir is created");
+    let path = dir.path().join("stempel.json");
+    std::fs::write(&path, STORAGE).expect("Storage is written");
+    (dir, path)
+}
+
+/// The binary on `storage` at the fixed time `now`, in UTC and without colors.
+fn stempel(storage: &Path, now: &str) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_stempel"));
+    cmd.env("TZ", "UTC")
+        .env("NO_COLOR", "1")
+        .env_remove("STEMPEL_STORAGE")
+        .arg("--storage")
+        .arg(storage)
+        .arg("--now")
+        .arg(now);
+    cmd
+}
+
+fn read_json(path: &Path) -> serde_json::Value {
+    let content = std::fs::read_to_string(path).expect("Storage is readable");
+    serde_json::from_str(&content).expect("Storage is json")
+}
+
+#[test]
+fn work_day_with_break() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("You started at 09:00"));
+    stempel(&path, "2024-03-05T12:00:00Z")
+        .args(["break", "start"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("after working 3:00h"));
+    stempel(&path, "2024-03-05T12:30:00Z")
+        .args(["break", "stop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("break for 0:30h"));
+    stempel(&path, "2024-03-05T17:30:00Z")
+        .args(["stop", "--note", "release"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("You worked 8:00h today"));
+
+    let json = read_json(&path);
+    assert!(json["start"].is_null());
+    let entry = &json["account"]["2024-03-05T17:30:00Z"];
+    assert_eq!(entry["secs"], 8 * 3600);
+    assert_eq!(entry["note"], "release");
+    assert_eq!(entry["breaks"][0][0], "2024-03-05T12:00:00Z");
+    assert_eq!(entry["breaks"][0][1]["secs"], 1800);
+
+    stempel(&path, "2024-03-05T18:00:00Z")
+        .arg("stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("05.03.2024 with 08:00h"));
+    let output = stempel(&path, "2024-03-05T18:00:00Z")
+        .args(["stats", "--json"])
+        .output()
+        .expect("Stats run");
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).expect("Stats are json");
+    assert_eq!(stats["total_secs"], 8 * 3600);
+    assert_eq!(stats["worked_days"], 1);
+    assert_eq!(stats["months"][1]["month"], 3);
+    assert_eq!(stats["months"][1]["total_secs"], 8 * 3600);
+}
+
+#[test]
+fn stop_without_start_fails() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T17:00:00Z")
+        .arg("stop")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("You did not start working"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), STORAGE);
+}
+
+#[test]
+fn double_start_fails() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T10:00:00Z")
+        .arg("start")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("You already started at 09:00"));
+    assert_eq!(read_json(&path)["start"], "2024-03-05T09:00:00Z");
+}
+
+#[test]
+fn missing_storage_fails() {
+    let dir = tempfile::tempdir().expect("Temp dir is created");
+    let path = dir.path().join("missing.json");
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("start")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("run 'stempel init' first"));
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .args(["configure", "--daily-hours", "8"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("run 'stempel init' first"));
+    assert!(!path.exists());
+}
+
+#[test]
+fn legacy_storage_is_read_only() {
+    let dir = tempfile::tempdir().expect("Temp dir is created");
+    let path = dir.path().join("stempel.json");
+    std::fs::write(&path, LEGACY).expect("Storage is written");
+    stempel(&path, "2024-03-05T18:00:00Z")
+        .arg("stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("05.03.2024 with 08:00h"))
+        .stderr(predicate::str::contains("run 'stempel migrate'"));
+    stempel(&path, "2024-03-05T18:00:00Z")
+        .arg("start")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("legacy format"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), LEGACY);
+}
+
+#[test]
+fn migrate_is_idempotent() {
+    let (dir, path) = storage();
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("migrate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("nothing to migrate"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), STORAGE);
+    assert!(!dir.path().join("stempel.json.bak").exists());
+
+    std::fs::write(&path, LEGACY).expect("Storage is written");
+    stempel(&path, "2024-03-05T18:00:00Z")
+        .arg("migrate")
+        .assert()
+        .success();
+    let migrated = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(read_json(&path)["name"], "test");
+    let backup = dir.path().join("stempel.json.bak");
+    assert!(std::fs::read_to_string(&backup)
+        .unwrap()
+        .contains("work_sets"));
+    std::fs::remove_file(&backup).unwrap();
+    stempel(&path, "2024-03-05T18:00:00Z")
+        .arg("migrate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("nothing to migrate"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), migrated);
+    assert!(!backup.exists());
+}
+
+#[test]
+fn stop_after_duration() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T10:00:00Z")
+        .args(["break", "duration", "0:30"])
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T18:00:00Z")
+        .args(["stop", "--duration", "3:00"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("You worked 2:30h today"));
+    assert_eq!(
+        read_json(&path)["account"]["2024-03-05T12:00:00Z"]["secs"],
+        150 * 60
+    );
+
+    stempel(&path, "2024-03-06T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-06T10:00:00Z")
+        .args(["break", "duration", "0:30"])
+        .assert()
+        .success();
+    stempel(&path, "2024-03-06T18:00:00Z")
+        .args(["stop", "--duration", "3:00", "--gross"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("You worked 3:00h today"));
+    assert_eq!(
+        read_json(&path)["account"]["2024-03-06T12:30:00Z"]["secs"],
+        3 * 3600
+    );
+}
+
+#[test]
+fn yes_skips_confirmation() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-04T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T08:00:00Z")
+        .args(["--yes", "stop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("You worked 23:00h today"));
+    assert_eq!(
+        read_json(&path)["account"]["2024-03-05T08:00:00Z"]["secs"],
+        23 * 3600
+    );
+}
+
+#[test]
+fn stats_by_pay_period() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-06-27T09:00:00Z")
+        .args(["configure", "--period-start-day", "26"])
+        .assert()
+        .success();
+    for (start, stop) in [
+        ("2024-05-27T09:00:00Z", "2024-05-27T18:00:00Z"),
+        ("2024-06-25T09:00:00Z", "2024-06-25T15:00:00Z"),
+        ("2024-06-26T09:00:00Z", "2024-06-26T19:00:00Z"),
+    ] {
+        stempel(&path, start).arg("start").assert().success();
+        stempel(&path, stop).arg("stop").assert().success();
+    }
+    stempel(&path, "2024-06-27T09:00:00Z")
+        .args(["stats", "--periods", "--months", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("last 1 pay periods"))
+        .stdout(
+            predicate::str::is_match("26 May – 25 Jun:\n(.*\n)*    Overtime: +-01:00h").unwrap(),
+        )
+        .stdout(
+            predicate::str::is_match("26 Jun – 25 Jul:\n(.*\n)*    Overtime: +02:00h").unwrap(),
+        );
+
+    let (_dir, path) = storage();
+    stempel(&path, "2024-06-27T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-06-27T10:00:00Z")
+        .args(["stats", "--periods"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--period-start-day"));
+}
+
+#[test]
+fn start_before_last_stop_asks() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T17:00:00Z")
+        .arg("stop")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T12:00:00Z")
+        .arg("start")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Did your clock go backwards?"))
+        .stdout(predicate::str::contains("Nothing started"));
+    assert!(read_json(&path)["start"].is_null());
+    stempel(&path, "2024-03-05T12:00:00Z")
+        .args(["start", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("You started at 12:00"));
+    assert_eq!(read_json(&path)["start"], "2024-03-05T12:00:00Z");
+}
+
+#[test]
+fn export_csv_range() {
+    let (_dir, path) = storage();
+    for (start, stop) in [
+        ("2024-01-05T09:00:00Z", "2024-01-05T17:30:00Z"),
+        ("2024-02-01T08:00:00Z", "2024-02-01T12:00:00Z"),
+    ] {
+        stempel(&path, start).arg("start").assert().success();
+        stempel(&path, stop).arg("stop").assert().success();
+    }
+    stempel(&path, "2024-02-02T09:00:00Z")
+        .args(["export", "--format", "csv", "--since", "2024-01-01"])
+        .args(["--until", "2024-01-31"])
+        .assert()
+        .success()
+        .stdout("date,start,stop,duration,note\n2024-01-05,09:00,17:30,8:30,\n");
+    stempel(&path, "2024-02-02T09:00:00Z")
+        .args(["export", "--format", "csv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2024-01-05,09:00,17:30,8:30,\n"))
+        .stdout(predicate::str::contains("2024-02-01,08:00,12:00,4:00,\n"));
+    stempel(&path, "2024-02-02T09:00:00Z")
+        .args(["export", "--format", "csv", "--since", "2024-03-01"])
+        .assert()
+        .success()
+        .stdout("date,start,stop,duration,note\n");
+    stempel(&path, "2024-02-02T09:00:00Z")
+        .args(["export", "--since", "2024-01-31", "--until", "2024-01-01"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("after its end"));
+}
+
+#[test]
+fn mark_sick_day() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .args(["mark", "2024-03-04", "sick"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("04.03.2024 is marked as sick day"));
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .args(["vacation", "2024-03-06"])
+        .assert()
+        .success();
+    let json = read_json(&path);
+    assert_eq!(json["day_markers"]["2024-03-04"], "sick");
+    assert_eq!(json["day_markers"]["2024-03-06"], "vacation");
+
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T17:00:00Z")
+        .arg("stop")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T18:00:00Z")
+        .arg("stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Days off:"))
+        .stdout(predicate::str::is_match("Monday +04.03.: +sick day").unwrap())
+        .stdout(predicate::str::is_match("Wednesday 06.03.: +vacation").unwrap());
+}
+
+#[test]
+fn migrate_moves_days_off() {
+    let (_dir, path) = storage();
+    std::fs::write(
+        &path,
+        STORAGE.replace(
+            r#""daily_hours":8"#,
+            r#""daily_hours":8,"holidays":["2024-03-04"]"#,
+        ),
+    )
+    .expect("Storage is written");
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("migrate")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Moved 1 holidays"));
+    let json = read_json(&path);
+    assert_eq!(json["day_markers"]["2024-03-04"], "holiday");
+    assert!(json["config"]["holidays"].is_null());
+}
+
+#[test]
+fn stop_on_break_suggests_end_break() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T12:00:00Z")
+        .args(["break", "start"])
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T17:30:00Z")
+        .arg("stop")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("on a break since 12:00"))
+        .stderr(predicate::str::contains(
+            "'stempel stop --time 17:30 --end-break'",
+        ));
+    stempel(&path, "2024-03-05T18:00:00Z")
+        .args(["stop", "--time", "17:30", "--end-break"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("You worked 3:00h today"));
+}
+
+#[test]
+fn standup_summarizes_yesterday_and_today() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-04T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-04T12:00:00Z")
+        .args(["break", "duration", "0:30"])
+        .assert()
+        .success();
+    stempel(&path, "2024-03-04T17:30:00Z")
+        .arg("stop")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T11:00:00Z")
+        .args(["break", "start"])
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T11:15:00Z")
+        .arg("standup")
+        .assert()
+        .success()
+        .stdout(
+            "Yesterday (Mon 04.03.): 08:00h worked, 00:30h breaks\n\
+             Today (Tue 05.03.): 02:00h worked, 00:15h breaks, on a break since 11:00\n",
+        );
+
+    stempel(&path, "2024-03-11T09:00:00Z")
+        .arg("standup")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Yesterday (Sun 10.03., weekend): nothing tracked",
+        ));
+    stempel(&path, "2024-03-12T09:00:00Z")
+        .args(["mark", "2024-03-11", "sick"])
+        .assert()
+        .success();
+    stempel(&path, "2024-03-12T09:00:00Z")
+        .arg("standup")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Yesterday (Mon 11.03., sick day): nothing tracked",
+        ));
+}
+
+#[test]
+fn keep_same_day_sessions() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T08:00:00Z")
+        .args(["configure", "--merge-same-day", "false"])
+        .assert()
+        .success();
+    for (start, stop) in [
+        ("2024-03-05T08:00:00Z", "2024-03-05T12:00:00Z"),
+        ("2024-03-05T13:00:00Z", "2024-03-05T16:30:00Z"),
+    ] {
+        stempel(&path, start).arg("start").assert().success();
+        stempel(&path, stop).arg("stop").assert().success();
+    }
+    assert_eq!(read_json(&path)["account"].as_object().unwrap().len(), 2);
+    stempel(&path, "2024-04-02T08:00:00Z")
+        .args(["stats", "--daily"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("07:30h  08:00–16:30"))
+        .stdout(predicate::str::contains("08:00–12:00       04:00h"))
+        .stdout(predicate::str::contains("13:00–16:30       03:30h"));
+    stempel(&path, "2024-03-06T08:00:00Z")
+        .arg("canonicalize")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "merging same-day entries is disabled",
+        ));
+
+    stempel(&path, "2024-03-06T08:00:00Z")
+        .args(["configure", "--merge-same-day", "true"])
+        .assert()
+        .success();
+    stempel(&path, "2024-03-06T08:00:00Z")
+        .args(["--yes", "canonicalize"])
+        .assert()
+        .success();
+    assert_eq!(read_json(&path)["account"].as_object().unwrap().len(), 1);
+}
+
+#[test]
+fn add_break_ending_now() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T12:00:00Z")
+        .args(["break", "add", "--duration", "0:30"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("You did not start working"));
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T12:00:00Z")
+        .args(["break", "add", "--duration", "0:30"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Added a break of 00:30h since 11:30.",
+        ));
+    stempel(&path, "2024-03-05T12:00:00Z")
+        .args(["break", "add", "2024-03-05", "--duration", "0:30"])
+        .assert()
+        .failure();
+    stempel(&path, "2024-03-05T17:30:00Z")
+        .arg("stop")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("You worked 8:00h today"));
+}
+
+#[test]
+fn split_over_midnight_keeps_spans() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T08:00:00Z")
+        .args(["configure", "--split-over-midnight", "true"])
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T23:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-06T06:00:00Z")
+        .arg("stop")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-06T08:00:00Z")
+        .args(["export", "--format", "csv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2024-03-05,23:00,00:00,1:00"))
+        .stdout(predicate::str::contains("2024-03-06,00:00,06:00,6:00"));
+    stempel(&path, "2024-03-06T08:00:00Z")
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No anomalies found."));
+}
+
+#[test]
+fn relative_to_given_now() {
+    let (_dir, path) = storage();
+    stempel(&path, "2030-01-07T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2030-01-07T12:00:00Z")
+        .args(["break", "start"])
+        .assert()
+        .success();
+    stempel(&path, "2030-01-07T17:00:00Z")
+        .args(["stop", "--end-break"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Finished your break of 05:00h"));
+    stempel(&path, "2030-01-08T08:00:00Z")
+        .args(["export", "--format", "csv", "--since", "yesterday"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2030-01-07,09:00,17:00,3:00"));
+}
+
+#[test]
+fn digest_of_current_or_given_week() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T17:00:00Z")
+        .arg("stop")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-06T08:00:00Z")
+        .arg("digest")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "**Week 10** (04.03.2024 – 10.03.2024)",
+        ));
+    stempel(&path, "2024-03-20T08:00:00Z")
+        .args(["digest", "--week", "10"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("**Week 10**"))
+        .stdout(predicate::str::contains("- Total: 08:00h"));
+    stempel(&path, "2024-03-20T08:00:00Z")
+        .args(["digest", "--week", "60"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("There is no week 60 in 2024"));
+}
+
+#[test]
+fn dry_run_keeps_storage() {
+    let (dir, path) = storage();
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .args(["--dry-run", "holiday", "2024-03-08"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("storage was not modified"));
+    stempel(&path, "2024-03-05T09:00:00Z")
+        .args(["--dry-run", "configure", "--daily-hours", "6"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("storage was not modified"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), STORAGE);
+
+    std::fs::write(&path, LEGACY).expect("Storage is written");
+    stempel(&path, "2024-03-05T18:00:00Z")
+        .args(["--dry-run", "migrate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would migrate"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), LEGACY);
+    assert!(!dir.path().join("stempel.json.bak").exists());
+}
+
+#[test]
+fn weekly_stats_count_midnight_stop_for_day_before() {
+    let (_dir, path) = storage();
+    stempel(&path, "2024-03-05T08:00:00Z")
+        .args(["configure", "--weekly-stats", "true"])
+        .assert()
+        .success();
+    stempel(&path, "2024-03-05T16:00:00Z")
+        .arg("start")
+        .assert()
+        .success();
+    stempel(&path, "2024-03-06T00:00:00Z")
+        .args(["stop", "--time", "00:00"])
+        .assert()
+        .success();
+    stempel(&path, "2024-03-06T08:00:00Z")
+        .arg("stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Tuesday   05.03."))
+        .stdout(predicate::str::contains("Wednesday 06.03.").not());
+}